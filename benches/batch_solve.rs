@@ -0,0 +1,97 @@
+use bevy::{
+    prelude::*,
+    tasks::{ComputeTaskPool, TaskPool},
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use walking_ik_spider::{
+    ik::{
+        solver::{fabrik::FabrikSolver, IkSolver},
+        IkChain, SolveSettings,
+    },
+    plugin::leg::{animate_leg_towards_target, AnimatedLeg},
+};
+
+const CHAIN_COUNTS: [usize; 3] = [1, 100, 10_000];
+
+/// Targets alternate between solves and there's no tolerance, so every solve runs all iterations
+const SETTINGS: SolveSettings = SolveSettings {
+    tolerance: 0.0,
+    max_iterations: 6,
+};
+
+/// A leg-like chain and the two targets it alternates between
+fn leg_chain(index: usize) -> (IkChain, [Vec3; 2]) {
+    let offset = Vec3::new(index as f32 * 4.0, 0.0, 0.0);
+    let points = vec![
+        offset,
+        offset + Vec3::new(1.0, 3.0, 0.0),
+        offset + Vec3::new(2.0, 0.0, 0.0),
+    ];
+    let targets = [
+        offset + Vec3::new(3.0, -0.5, 1.0),
+        offset + Vec3::new(2.0, -0.5, -1.0),
+    ];
+
+    let chain = IkChain::new(points).unwrap().with_settings(SETTINGS);
+    (chain, targets)
+}
+
+fn solve_sequential(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve_sequential");
+
+    for count in CHAIN_COUNTS {
+        let (mut chains, targets): (Vec<IkChain>, Vec<[Vec3; 2]>) =
+            (0..count).map(leg_chain).unzip();
+        let mut flip = 0;
+
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_function(BenchmarkId::from_parameter(count), |b| {
+            b.iter(|| {
+                flip ^= 1;
+                for (chain, targets) in chains.iter_mut().zip(targets.iter()) {
+                    FabrikSolver.solve(chain, targets[flip]).unwrap();
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn solve_parallel_system(c: &mut Criterion) {
+    ComputeTaskPool::init(TaskPool::default);
+
+    let mut group = c.benchmark_group("solve_parallel_system");
+
+    for count in CHAIN_COUNTS {
+        let mut world = World::new();
+        world.insert_resource(Time::default());
+        world.spawn_batch((0..count).map(|index| {
+            let (chain, targets) = leg_chain(index);
+            let mut leg = AnimatedLeg::new(Vec3::ZERO, targets[0]);
+            leg.current_target = targets[1];
+            (chain, leg)
+        }));
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems((swap_leg_targets, animate_leg_towards_target).chain());
+
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_function(BenchmarkId::from_parameter(count), |b| {
+            b.iter(|| schedule.run(&mut world))
+        });
+    }
+
+    group.finish();
+}
+
+/// Time never advances here, so each leg is solved towards its previous target, which this swaps every run
+fn swap_leg_targets(mut legs: Query<&mut AnimatedLeg>) {
+    for mut leg in legs.iter_mut() {
+        let previous = leg.previous_target;
+        leg.set_new_target(previous);
+    }
+}
+
+criterion_group!(benches, solve_sequential, solve_parallel_system);
+criterion_main!(benches);
@@ -0,0 +1,161 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use glam::Vec3;
+use walking_ik_spider::{
+    ik::{
+        solver::{
+            ccd::CcdSolver,
+            constrain_chain,
+            fabrik::{backward_fabrik_pass, forward_fabrik_pass, FabrikSolver},
+            jacobian::JacobianSolver,
+            ChainSolver, IkSolver,
+        },
+        IkChain, JointConstraint, PoleTarget, SolveSettings,
+    },
+    rotations::looking_towards,
+};
+
+const SEGMENT_COUNTS: [usize; 4] = [2, 4, 16, 64];
+const ITERATION_COUNTS: [u32; 4] = [1, 4, 16, 64];
+
+const SOLVERS: [(&str, ChainSolver); 3] = [
+    ("fabrik", ChainSolver::Fabrik(FabrikSolver)),
+    ("ccd", ChainSolver::Ccd(CcdSolver)),
+    (
+        "jacobian",
+        ChainSolver::Jacobian(JacobianSolver { damping: 0.5 }),
+    ),
+];
+
+/// A zigzag chain of unit segments along the x axis, bent so the solvers have something to straighten
+fn zigzag_chain(segments: usize, max_iterations: u32) -> IkChain {
+    let points = (0..=segments)
+        .map(|i| Vec3::new(i as f32 * 0.8, (i % 2) as f32 * 0.6, 0.0))
+        .collect();
+
+    // Without a tolerance a solve always runs every iteration, unless it hits the target exactly
+    let settings = SolveSettings {
+        tolerance: 0.0,
+        max_iterations,
+    };
+
+    IkChain::new(points).unwrap().with_settings(settings)
+}
+
+/// Two targets within reach of the chain, solves alternate between them so the chain never stays solved
+fn reachable_targets(segments: usize) -> [Vec3; 2] {
+    let reach = segments as f32 * 0.5;
+    [
+        Vec3::new(reach, reach * 0.5, 1.0),
+        Vec3::new(reach, -reach * 0.5, -1.0),
+    ]
+}
+
+fn unreachable_targets(segments: usize) -> [Vec3; 2] {
+    let reach = segments as f32 * 2.0;
+    [Vec3::new(reach, 1.0, 1.0), Vec3::new(-reach, 1.0, -1.0)]
+}
+
+fn solve_chain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve_chain");
+
+    for (name, solver) in SOLVERS {
+        for segments in SEGMENT_COUNTS {
+            let targets = [
+                ("reachable", reachable_targets(segments)),
+                ("unreachable", unreachable_targets(segments)),
+            ];
+
+            for (reach, targets) in targets {
+                let mut chain = zigzag_chain(segments, 8);
+                let mut flip = 0;
+
+                let id = BenchmarkId::new(format!("{}/{}", name, reach), segments);
+                group.bench_function(id, |b| {
+                    b.iter(|| {
+                        flip ^= 1;
+                        solver.solve(&mut chain, targets[flip]).unwrap()
+                    })
+                });
+            }
+        }
+    }
+
+    group.finish();
+}
+
+fn solve_iterations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve_iterations");
+    let targets = reachable_targets(8);
+
+    for (name, solver) in SOLVERS {
+        for iterations in ITERATION_COUNTS {
+            let mut chain = zigzag_chain(8, iterations);
+            let mut flip = 0;
+
+            group.bench_function(BenchmarkId::new(name, iterations), |b| {
+                b.iter(|| {
+                    flip ^= 1;
+                    solver.solve(&mut chain, targets[flip]).unwrap()
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn fabrik_passes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fabrik_passes");
+
+    for segments in SEGMENT_COUNTS {
+        let target = reachable_targets(segments)[0];
+
+        let mut chain = zigzag_chain(segments, 1);
+        group.bench_function(BenchmarkId::new("forward", segments), |b| {
+            b.iter(|| forward_fabrik_pass(&mut chain, target))
+        });
+
+        let mut chain = zigzag_chain(segments, 1);
+        group.bench_function(BenchmarkId::new("backward", segments), |b| {
+            b.iter(|| backward_fabrik_pass(&mut chain, target))
+        });
+    }
+
+    group.finish();
+}
+
+/// Constraining a plain chain, and one with a pole, cone limits on every joint and an end orientation
+fn constrain(c: &mut Criterion) {
+    let mut group = c.benchmark_group("constrain_chain");
+
+    for segments in SEGMENT_COUNTS {
+        let target = reachable_targets(segments)[0];
+        let constraints = vec![JointConstraint::Cone { max_angle: 0.8 }; segments];
+
+        let mut chain = zigzag_chain(segments, 1)
+            .with_pole(PoleTarget::Direction(Vec3::Y))
+            .with_end_orientation(looking_towards(Vec3::NEG_Y, Vec3::NEG_Z))
+            .with_constraints(constraints)
+            .unwrap();
+
+        group.bench_function(BenchmarkId::new("constrained", segments), |b| {
+            b.iter(|| constrain_chain(&mut chain, target))
+        });
+
+        let mut chain = zigzag_chain(segments, 1);
+        group.bench_function(BenchmarkId::new("unconstrained", segments), |b| {
+            b.iter(|| constrain_chain(&mut chain, target))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    solve_chain,
+    solve_iterations,
+    fabrik_passes,
+    constrain
+);
+criterion_main!(benches);
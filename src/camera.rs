@@ -1,6 +1,7 @@
 use bevy::prelude::*;
+use walking_ik_spider::rotations;
 
-use crate::{rotations, spider::Spider};
+use crate::spider::Spider;
 
 const FOLLOW_DISTANCE: f32 = 10.0;
 const SPAWN_POSITION: Vec3 = Vec3::new(0.0, 6.0, 10.0);
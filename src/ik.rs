@@ -1,189 +1,522 @@
-pub mod leg;
+pub mod solver;
+pub mod tree;
 
-use crate::rotations;
-use bevy::prelude::*;
-use leg::IkLegPlugin;
-
-const POINT_RADIUS: f32 = 0.3;
-const POINT_COLOR: Color = Color::PURPLE;
-const SEGMENT_COLOR: Color = Color::CYAN;
+use std::fmt;
 
-const DRAW_CHAIN_GIZMOS: bool = false;
-const DRAW_ORIENTATION_GIZMOS: bool = false;
+use glam::{Quat, Vec3};
 
-pub struct IkPlugin;
-
-impl Plugin for IkPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_plugins(IkLegPlugin)
-            .add_systems(Update, draw_ik_chain_gizmos);
-    }
-}
+use crate::rotations;
 
-#[derive(Component)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+#[derive(Clone, Debug)]
 pub struct IkChain {
-    /// Position this chain starts at, in world space
+    /// Position this chain starts at, in the space of the chain
     pub start: Vec3,
     points: Vec<Vec3>,
     lengths: Vec<f32>,
+    /// One orientation per segment, each looks along its segment with negative z and carries the twist down the chain
+    orientations: Vec<Quat>,
+    /// One constraint per joint, the joint at index i rotates the segment starting at points\[i\]
+    constraints: Vec<JointConstraint>,
+    /// Where the middle joints bend towards, if not set they bend upwards
+    pub pole: Option<PoleTarget>,
+    /// Orientation the last segment should end up in, the segment points along its negative z axis.
+    /// Only honoured for chains of at least 2 segments, and it overrides the constraint of the last joint.
+    pub end_orientation: Option<Quat>,
+    /// The space the start, points, targets and pole of this chain are in
+    pub space: ChainSpace,
+    pub settings: SolveSettings,
+    last_report: SolveReport,
 }
 
 impl IkChain {
-    pub fn new(points: Vec<Vec3>) -> Self {
+    /// Make a chain through the given points, the segment lengths are the distances between them
+    pub fn new(points: Vec<Vec3>) -> Result<Self, IkError> {
         if points.len() < 2 {
-            panic!(
-                "Invalid vector! IK chain can't be made from {} points",
-                points.len()
-            );
+            return Err(IkError::TooFewPoints {
+                count: points.len(),
+            });
+        }
+
+        if !points.iter().all(|point| point.is_finite()) {
+            return Err(IkError::NonFiniteInput);
         }
 
         let lengths = calculate_chain_lengths(&points);
+        if let Some(index) = lengths.iter().position(|&length| length <= f32::EPSILON) {
+            return Err(IkError::DegenerateSegment { index });
+        }
+
+        let constraints = vec![JointConstraint::Unconstrained; lengths.len()];
+        let orientations = vec![Quat::IDENTITY; lengths.len()];
 
-        IkChain {
+        let mut chain = IkChain {
             start: points[0],
             points,
             lengths,
+            orientations,
+            constraints,
+            pole: None,
+            end_orientation: None,
+            space: ChainSpace::default(),
+            settings: SolveSettings::default(),
+            last_report: SolveReport::default(),
+        };
+
+        chain.reset_orientations();
+        Ok(chain)
+    }
+
+    pub fn with_pole(mut self, pole: PoleTarget) -> Self {
+        self.pole = Some(pole);
+        self.reset_orientations();
+        self
+    }
+
+    pub fn with_end_orientation(mut self, orientation: Quat) -> Self {
+        self.end_orientation = Some(orientation);
+        self.reset_orientations();
+        self
+    }
+
+    pub fn with_space(mut self, space: ChainSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    pub fn with_settings(mut self, settings: SolveSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Set the joint constraints of this chain, joints without a given constraint are left unconstrained
+    pub fn with_constraints(mut self, constraints: Vec<JointConstraint>) -> Result<Self, IkError> {
+        if constraints.len() > self.lengths.len() {
+            return Err(IkError::IndexOutOfRange {
+                index: constraints.len() - 1,
+                len: self.lengths.len(),
+            });
         }
+
+        for (index, constraint) in constraints.into_iter().enumerate() {
+            self.constraints[index] = constraint;
+        }
+
+        Ok(self)
     }
 
-    pub fn get_segment(&self, index: usize) -> ChainSegment {
-        if index >= self.points.len() - 1 {
-            panic!(
-                "Invalid index! get_segment called with index: {}, but only {} points",
+    pub fn get_constraint(&self, index: usize) -> Result<JointConstraint, IkError> {
+        self.constraints
+            .get(index)
+            .copied()
+            .ok_or(IkError::IndexOutOfRange {
                 index,
-                self.points.len()
-            );
+                len: self.constraints.len(),
+            })
+    }
+
+    pub fn get_segment(&self, index: usize) -> Result<ChainSegment, IkError> {
+        if index >= self.lengths.len() {
+            return Err(IkError::IndexOutOfRange {
+                index,
+                len: self.lengths.len(),
+            });
         }
 
+        Ok(self.segment(index))
+    }
+
+    /// Same as `get_segment`, for indices that are known to be valid
+    fn segment(&self, index: usize) -> ChainSegment {
         ChainSegment {
             start: self.points[index],
             end: self.points[index + 1],
             length: self.lengths[index],
+            orientation: self.orientations[index],
         }
     }
 
-    /// Move the start position by the given delta
-    pub fn move_start(&mut self, delta: Vec3) {
-        self.start += delta;
+    pub fn segment_lengths(&self) -> &[f32] {
+        &self.lengths
     }
-}
 
-pub struct ChainSegment {
-    pub start: Vec3,
-    pub end: Vec3,
-    pub length: f32,
-}
+    /// Change the length of a segment, the points after it move along so the rest of the chain keeps its shape
+    pub fn set_segment_length(&mut self, index: usize, length: f32) -> Result<(), IkError> {
+        if index >= self.lengths.len() {
+            return Err(IkError::IndexOutOfRange {
+                index,
+                len: self.lengths.len(),
+            });
+        }
 
-fn solve_chain_towards_target(
-    chain: &mut IkChain,
-    target: Vec3,
-    iterations: i32,
-    gizmos: &mut Gizmos,
-) {
-    for _ in 0..iterations {
-        backward_fabrik_pass(chain, target);
-        forward_fabrik_pass(chain);
-        constrain_chain_orientation(chain, gizmos);
-    }
-}
+        if !length.is_finite() {
+            return Err(IkError::NonFiniteInput);
+        }
 
-fn forward_fabrik_pass(chain: &mut IkChain) {
-    let points_count = chain.points.len();
+        if length <= f32::EPSILON {
+            return Err(IkError::DegenerateSegment { index });
+        }
 
-    chain.points[0] = chain.start;
-    for i in 0..points_count - 1 {
-        let segment = chain.get_segment(i);
+        let segment = self.segment(index);
         let direction = (segment.end - segment.start).normalize_or_zero();
+        let offset = direction * (length - segment.length);
+
+        for point in self.points[index + 1..].iter_mut() {
+            *point += offset;
+        }
 
-        chain.points[i + 1] = segment.start + direction * segment.length;
+        self.lengths[index] = length;
+        Ok(())
     }
-}
 
-fn backward_fabrik_pass(chain: &mut IkChain, target: Vec3) {
-    let points_count = chain.points.len();
+    /// How many segments this chain is made of, this is also the number of joints
+    pub fn segment_count(&self) -> usize {
+        self.lengths.len()
+    }
 
-    chain.points[points_count - 1] = target;
-    for i in (0..points_count - 1).rev() {
-        let segment = chain.get_segment(i);
-        let direction = (segment.start - segment.end).normalize_or_zero();
+    /// Set the start position to the given position, in the space of the chain
+    pub fn set_start(&mut self, position: Vec3) {
+        self.start = position;
+    }
 
-        chain.points[i] = segment.end + direction * segment.length;
+    pub fn points(&self) -> &[Vec3] {
+        &self.points
+    }
+
+    /// The last point of the chain, this is the point that gets moved towards the target
+    pub fn end(&self) -> Vec3 {
+        self.points[self.points.len() - 1]
+    }
+
+    /// The length of the chain when fully stretched
+    pub fn total_length(&self) -> f32 {
+        self.lengths.iter().sum()
+    }
+
+    /// Returns how far the target is outside the reach of this chain, 0 if it can be reached.
+    /// A target can be too far away, or closer to the start than the chain can fold back to.
+    pub fn overshoot(&self, target: Vec3) -> f32 {
+        let (min_reach, max_reach, distance) = self.reach_towards(target);
+
+        (distance - max_reach).max(min_reach - distance).max(0.0)
+    }
+
+    /// Returns whether the target is farther from the start than the chain can stretch
+    fn is_too_far(&self, target: Vec3) -> bool {
+        let (_, max_reach, distance) = self.reach_towards(target);
+        distance > max_reach
+    }
+
+    /// Returns the closest and farthest the effector can get from the start, and how far its target is from the start.
+    /// The closest is the longest segment folded back over all the others.
+    fn reach_towards(&self, target: Vec3) -> (f32, f32, f32) {
+        let (effector, effector_target) = self.effector(target);
+        let lengths = &self.lengths[..effector];
+        let max_reach: f32 = lengths.iter().sum();
+        let longest = lengths.iter().copied().fold(0.0, f32::max);
+
+        (
+            (2.0 * longest - max_reach).max(0.0),
+            max_reach,
+            self.start.distance(effector_target),
+        )
+    }
+
+    /// Points the chain in a straight line from the start towards the target,
+    /// apart from the last segment if it has an end orientation.
+    /// This ignores the joint constraints, run `solver::constrain_chain` afterwards to keep the joints within them.
+    pub fn straighten_towards(&mut self, target: Vec3) {
+        let (effector, effector_target) = self.effector(target);
+        let direction = (effector_target - self.start)
+            .try_normalize()
+            .unwrap_or(Vec3::NEG_Z);
+
+        self.points[0] = self.start;
+        for i in 0..effector {
+            self.points[i + 1] = self.points[i] + direction * self.lengths[i];
+        }
+
+        if let Some(end_direction) = self
+            .end_direction()
+            .filter(|_| effector < self.lengths.len())
+        {
+            self.points[effector + 1] =
+                self.points[effector] + end_direction * self.lengths[effector];
+        }
     }
-}
 
-fn constrain_chain_orientation(chain: &mut IkChain, gizmos: &mut Gizmos) {
-    // After learning about rotations and asking chat-gpt, here's my plan:
-    // 1. use the first and last points to calculate the leg's local orientation (the leg points in the negative z direction)
-    // 2. calculate the orientation from the first point to the middle joint
-    // 3. get the delta quaternion and convert it to euler angle
-    // 4. get the delta components to constrain (y & x)
-    // 5. adjust the quaternion towards the middle joint by these delta components so it aligns with the leg's orientation
-    // 6. place the middle joint on this new position
-    // 7. repeat for each middle joint <- this last part isn't implemented, but also not needed in this case
+    /// Direction the last segment should point in, if it has an end orientation
+    fn end_direction(&self) -> Option<Vec3> {
+        self.end_orientation
+            .map(|orientation| (orientation * Vec3::NEG_Z).normalize_or_zero())
+    }
+
+    /// Whether the pole and end orientation can be solved towards, they have to be finite and the end orientation can't be zero
+    fn has_valid_goals(&self) -> bool {
+        let pole_is_finite = match self.pole {
+            Some(pole) => pole.position(self.start).is_finite(),
+            None => true,
+        };
+        let end_is_valid = match self.end_orientation {
+            Some(orientation) => {
+                orientation.is_finite() && orientation.length_squared() > f32::EPSILON
+            }
+            None => true,
+        };
+
+        pole_is_finite && end_is_valid
+    }
+
+    /// Returns the index of the point that solvers move onto a target, and that target.
+    /// This is the end of the chain, or the start of the last segment if the chain has an end orientation.
+    fn effector(&self, target: Vec3) -> (usize, Vec3) {
+        let last_index = self.points.len() - 1;
+
+        match self.end_direction() {
+            Some(end_direction) if last_index >= 2 => (
+                last_index - 1,
+                target - end_direction * self.lengths[last_index - 1],
+            ),
+            _ => (last_index, target),
+        }
+    }
 
-    let first_point = chain.points[0];
-    let last_point = chain.points[chain.points.len() - 1];
-    let leg_orientation = rotations::looking_at(first_point, last_point, Vec3::Y);
+    /// Turns every segment orientation along with its segment, the solvers do this after every solve.
+    ///
+    /// Each orientation takes the shortest rotation from the old direction of its segment to the new one,
+    /// so the twist carries over from the last solve and never flips when the chain passes through vertical.
+    fn update_orientations(&mut self) {
+        for i in 0..self.lengths.len() {
+            let direction = (self.points[i + 1] - self.points[i]).normalize_or_zero();
+            let previous_direction = self.orientations[i] * Vec3::NEG_Z;
+
+            if direction != Vec3::ZERO {
+                self.orientations[i] = (Quat::from_rotation_arc(previous_direction, direction)
+                    * self.orientations[i])
+                    .normalize();
+            }
+        }
 
-    let middle_point = chain.points[1];
-    let joint_orientation = rotations::looking_at(first_point, middle_point, Vec3::Y);
+        self.orient_last_segment();
+    }
 
-    let delta_orientation = leg_orientation.inverse() * joint_orientation;
-    let delta_euler = delta_orientation.to_euler(EulerRot::XYZ);
+    /// Builds the segment orientations from the points alone, used when the chain or its pole is set up.
+    ///
+    /// The first segment is rolled so its up side faces the pole, every segment after that takes the shortest rotation
+    /// from the one before it.
+    fn reset_orientations(&mut self) {
+        let up = self
+            .pole
+            .map_or(Vec3::Y, |pole| pole.position(self.start) - self.start);
+
+        // Same reference the first joint gets constrained in, but towards the end since there's no target here
+        let mut orientation = rotations::looking_at(self.points[0], self.end(), up);
+
+        for i in 0..self.lengths.len() {
+            let direction = (self.points[i + 1] - self.points[i]).normalize_or_zero();
+            let local_direction = (orientation.inverse() * direction)
+                .try_normalize()
+                .unwrap_or(Vec3::NEG_Z);
+
+            orientation =
+                (orientation * Quat::from_rotation_arc(Vec3::NEG_Z, local_direction)).normalize();
+            self.orientations[i] = orientation;
+        }
 
-    let x_adjustment = if delta_euler.0 < 0.01 {
-        -delta_euler.0 + 0.01
-    } else {
-        0.0
-    };
+        self.orient_last_segment();
+    }
 
-    let orientation_adjustment = Quat::from_euler(EulerRot::XYZ, x_adjustment, -delta_euler.1, 0.0);
+    fn orient_last_segment(&mut self) {
+        // An oriented last segment gets placed along its end orientation, so it can take its twist as well
+        let last_index = self.lengths.len() - 1;
+        if let Some(end_orientation) = self.end_orientation.filter(|_| last_index >= 1) {
+            self.orientations[last_index] = end_orientation;
+        }
+    }
 
-    // calculate new direction
-    let adjusted_orientation = joint_orientation * orientation_adjustment;
-    let segment = chain.get_segment(0);
+    /// How the last solve of this chain went
+    pub fn last_report(&self) -> SolveReport {
+        self.last_report
+    }
 
-    // place middle point on new position
-    chain.points[1] = first_point + adjusted_orientation * (Vec3::NEG_Z * segment.length);
+    /// Returns true if the chain is attached to its start and the end is within tolerance of the target
+    fn is_solved(&self, target: Vec3) -> bool {
+        let tolerance = self.settings.tolerance;
+        let (effector, effector_target) = self.effector(target);
 
-    // For debugging and visualizing
-    if DRAW_ORIENTATION_GIZMOS {
-        draw_orientation_gizmos(gizmos, first_point, leg_orientation);
-        draw_orientation_gizmos(gizmos, first_point, joint_orientation);
+        self.points[0].distance(self.start) <= tolerance
+            && self.points[effector].distance(effector_target) <= tolerance
+            && self.end().distance(target) <= tolerance
     }
 }
 
-fn calculate_chain_lengths(points: &Vec<Vec3>) -> Vec<f32> {
-    let mut lengths: Vec<f32> = Vec::new();
+/// The space an IK chain is defined in. The solvers don't care about this,
+/// it tells users of the chain how to convert between the chain and the world.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChainSpace {
+    #[default]
+    World,
+    /// Relative to whatever the chain is attached to, like the entity holding it.
+    /// The chain moves, rotates and scales along with it without having to update the start.
+    Local,
+}
 
-    for index in 0..points.len() - 1 {
-        let start = points[index];
-        let end = points[index + 1];
-        lengths.push(start.distance(end));
+/// Controls the direction the middle joints of a chain bend in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PoleTarget {
+    /// A point in the space of the chain to bend towards
+    Point(Vec3),
+    /// A direction to bend in, relative to the start of the chain
+    Direction(Vec3),
+}
+
+impl PoleTarget {
+    /// Returns the point to bend towards for a chain starting at the given position
+    pub fn position(&self, chain_start: Vec3) -> Vec3 {
+        match *self {
+            PoleTarget::Point(point) => point,
+            PoleTarget::Direction(direction) => chain_start + direction,
+        }
     }
+}
 
-    lengths
+/// Limits how much work a solver does on a chain
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolveSettings {
+    /// Distance from the target at which the chain counts as solved
+    pub tolerance: f32,
+    pub max_iterations: u32,
+}
+
+impl Default for SolveSettings {
+    fn default() -> Self {
+        SolveSettings {
+            tolerance: 0.01,
+            max_iterations: 6,
+        }
+    }
 }
 
-// Gizmos
+/// Describes the outcome of solving a chain
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolveReport {
+    /// How many iterations were run, 0 if the chain was already solved
+    pub iterations: u32,
+    /// Distance between the end of the chain and the target after solving
+    pub error: f32,
+    /// False if the target is farther from the start than the chain is long,
+    /// or closer to it than the chain can fold back to
+    pub reachable: bool,
+    /// How far the target is outside the reach of the chain, 0 if it's reachable
+    pub overshoot: f32,
+}
+
+impl Default for SolveReport {
+    fn default() -> Self {
+        SolveReport {
+            iterations: 0,
+            error: 0.0,
+            reachable: true,
+            overshoot: 0.0,
+        }
+    }
+}
+
+/// Everything that can go wrong when building, accessing or solving an IK chain
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IkError {
+    /// A chain needs at least 2 points
+    TooFewPoints { count: usize },
+    /// Two consecutive points are at the same position, so the segment between them has no direction
+    DegenerateSegment { index: usize },
+    /// A segment or joint index that doesn't exist, `len` is how many there are
+    IndexOutOfRange { index: usize, len: usize },
+    /// A point, target, pole or end orientation contains NaN or infinity, or the end orientation is zero
+    NonFiniteInput,
+}
 
-fn draw_ik_chain_gizmos(mut gizmos: Gizmos, ik_chains: Query<&IkChain>) {
-    if DRAW_CHAIN_GIZMOS {
-        for chain in ik_chains.iter() {
-            for point in chain.points.iter() {
-                gizmos.sphere(*point, Quat::IDENTITY, POINT_RADIUS, POINT_COLOR);
+impl fmt::Display for IkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IkError::TooFewPoints { count } => {
+                write!(f, "IK chain can't be made from {} points", count)
+            }
+            IkError::DegenerateSegment { index } => {
+                write!(f, "segment {} of the IK chain has zero length", index)
             }
+            IkError::IndexOutOfRange { index, len } => {
+                write!(
+                    f,
+                    "index {} is out of range, the IK chain has {}",
+                    index, len
+                )
+            }
+            IkError::NonFiniteInput => write!(f, "IK input contains NaN or infinite values"),
+        }
+    }
+}
 
-            for index in 0..chain.points.len() - 1 {
-                let segment = chain.get_segment(index);
-                gizmos.line(segment.start, segment.end, SEGMENT_COLOR);
+impl std::error::Error for IkError {}
+
+pub struct ChainSegment {
+    pub start: Vec3,
+    pub end: Vec3,
+    pub length: f32,
+    /// Looks from the start to the end along negative z, including the twist of the segment
+    pub orientation: Quat,
+}
+
+/// Limits the direction a joint can point its segment in.
+///
+/// Directions are relative to the joint's reference orientation, which looks along the previous segment
+/// (or from the chain start towards the target for the first joint), so the unrotated direction is negative z.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum JointConstraint {
+    #[default]
+    Unconstrained,
+    /// Only rotate around `axis`, with the angle in radians between `min_angle` and `max_angle`
+    Hinge {
+        axis: Vec3,
+        min_angle: f32,
+        max_angle: f32,
+    },
+    /// Stay within `max_angle` radians of the reference direction
+    Cone { max_angle: f32 },
+}
+
+impl JointConstraint {
+    /// Returns the closest allowed direction to the given local direction
+    pub fn constrain(&self, direction: Vec3) -> Vec3 {
+        match *self {
+            JointConstraint::Unconstrained => direction.try_normalize().unwrap_or(Vec3::NEG_Z),
+            JointConstraint::Hinge {
+                axis,
+                min_angle,
+                max_angle,
+            } => {
+                let axis = axis.try_normalize().unwrap_or(Vec3::X);
+                let forward = rotations::project_on_plane(Vec3::NEG_Z, axis)
+                    .try_normalize()
+                    .unwrap_or_else(|| axis.any_orthonormal_vector());
+
+                let angle = rotations::angle_between_on_plane(forward, direction, axis);
+                Quat::from_axis_angle(axis, angle.clamp(min_angle, max_angle)) * forward
+            }
+            JointConstraint::Cone { max_angle } => {
+                rotations::clamp_to_cone(direction, Vec3::NEG_Z, max_angle)
             }
         }
     }
 }
 
-fn draw_orientation_gizmos(gizmos: &mut Gizmos, position: Vec3, orientation: Quat) {
-    gizmos.ray(position, orientation * Vec3::X, Color::GREEN);
-    gizmos.ray(position, orientation * Vec3::Y, Color::RED);
-    gizmos.ray(position, orientation * Vec3::Z, Color::BLUE);
+fn calculate_chain_lengths(points: &[Vec3]) -> Vec<f32> {
+    let mut lengths: Vec<f32> = Vec::new();
+
+    for index in 0..points.len() - 1 {
+        let start = points[index];
+        let end = points[index + 1];
+        lengths.push(start.distance(end));
+    }
+
+    lengths
 }
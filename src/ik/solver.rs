@@ -0,0 +1,147 @@
+pub mod ccd;
+pub mod fabrik;
+pub mod jacobian;
+
+use crate::rotations;
+use ccd::CcdSolver;
+use fabrik::FabrikSolver;
+use glam::{Quat, Vec3};
+use jacobian::JacobianSolver;
+
+use super::{IkChain, IkError, SolveReport};
+
+/// An algorithm that moves the points of an IK chain so its end reaches towards a target
+pub trait IkSolver {
+    /// Run a single iteration of the algorithm
+    fn iterate(&self, chain: &mut IkChain, target: Vec3);
+
+    /// Iterate until the chain is within tolerance of the target or the iteration cap of the chain is reached,
+    /// the returned report is also stored on the chain.
+    ///
+    /// Targets too far to reach aren't iterated on, the chain gets straightened towards them instead
+    /// and then bent as little as its joint constraints require.
+    /// Targets too close to reach are iterated on like any other, but still reported as unreachable.
+    fn solve(&self, chain: &mut IkChain, target: Vec3) -> Result<SolveReport, IkError> {
+        if !target.is_finite() || !chain.start.is_finite() || !chain.has_valid_goals() {
+            return Err(IkError::NonFiniteInput);
+        }
+
+        let overshoot = chain.overshoot(target);
+        if chain.is_too_far(target) {
+            chain.straighten_towards(target);
+            constrain_chain(chain, target);
+            chain.update_orientations();
+            chain.last_report = SolveReport {
+                iterations: 0,
+                error: chain.end().distance(target),
+                reachable: false,
+                overshoot,
+            };
+
+            return Ok(chain.last_report);
+        }
+
+        let mut iterations = 0;
+
+        while iterations < chain.settings.max_iterations && !chain.is_solved(target) {
+            self.iterate(chain, target);
+            iterations += 1;
+        }
+
+        chain.update_orientations();
+        chain.last_report = SolveReport {
+            iterations,
+            error: chain.end().distance(target),
+            reachable: overshoot <= 0.0,
+            overshoot,
+        };
+
+        Ok(chain.last_report)
+    }
+}
+
+/// Selects which solver is used for the chain on the same entity, chains without this component use FABRIK
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+#[derive(Clone, Copy, Debug)]
+pub enum ChainSolver {
+    Fabrik(FabrikSolver),
+    Ccd(CcdSolver),
+    Jacobian(JacobianSolver),
+}
+
+impl Default for ChainSolver {
+    fn default() -> Self {
+        ChainSolver::Fabrik(FabrikSolver)
+    }
+}
+
+impl IkSolver for ChainSolver {
+    fn iterate(&self, chain: &mut IkChain, target: Vec3) {
+        match self {
+            ChainSolver::Fabrik(solver) => solver.iterate(chain, target),
+            ChainSolver::Ccd(solver) => solver.iterate(chain, target),
+            ChainSolver::Jacobian(solver) => solver.iterate(chain, target),
+        }
+    }
+}
+
+/// Places each point from the start outwards, keeping the segment lengths and applying the pole target
+/// and joint constraints along the way
+pub fn constrain_chain(chain: &mut IkChain, target: Vec3) {
+    let points_count = chain.points.len();
+
+    // The up direction of the chain points towards the pole, so hinges bending 'up' bend towards the pole
+    let pole = chain.pole.map(|pole| pole.position(chain.start));
+    let up = pole.map_or(Vec3::Y, |pole| pole - chain.start);
+
+    if let Some(pole) = pole {
+        rotate_middle_joints_towards_pole(chain, pole, target);
+    }
+
+    // With an end orientation the last segment isn't constrained, it just points in the given direction
+    let (effector, _) = chain.effector(target);
+    let end_direction = chain
+        .end_direction()
+        .filter(|_| effector < points_count - 1);
+
+    // The first joint is constrained relative to the direction from the start to the target,
+    // every joint after that relative to the segment before it
+    let mut reference_orientation = rotations::looking_at(chain.start, target, up);
+
+    chain.points[0] = chain.start;
+    for i in 0..points_count - 1 {
+        let segment = chain.segment(i);
+        let direction = (segment.end - segment.start).normalize_or_zero();
+
+        let local_direction = reference_orientation.inverse() * direction;
+        let constrained_direction = match end_direction {
+            Some(end_direction) if i == effector => {
+                (reference_orientation.inverse() * end_direction).normalize_or_zero()
+            }
+            _ => chain.constraints[i].constrain(local_direction),
+        };
+
+        chain.points[i + 1] =
+            segment.start + reference_orientation * constrained_direction * segment.length;
+
+        reference_orientation *= Quat::from_rotation_arc(Vec3::NEG_Z, constrained_direction);
+    }
+}
+
+/// Rotates each middle joint around the line between its neighbours, so it ends up on the side facing the pole.
+/// The start of an oriented last segment isn't a middle joint, since it has to stay on its own target.
+fn rotate_middle_joints_towards_pole(chain: &mut IkChain, pole: Vec3, target: Vec3) {
+    let (effector, _) = chain.effector(target);
+
+    for i in 1..effector {
+        let previous = chain.points[i - 1];
+        let Some(axis) = (chain.points[i + 1] - previous).try_normalize() else {
+            continue;
+        };
+
+        let to_joint = chain.points[i] - previous;
+        let angle = rotations::angle_between_on_plane(to_joint, pole - previous, axis);
+
+        chain.points[i] = previous + Quat::from_axis_angle(axis, angle) * to_joint;
+    }
+}
@@ -0,0 +1,36 @@
+use glam::{Quat, Vec3};
+
+use super::{constrain_chain, IkSolver};
+use crate::ik::IkChain;
+
+/// Cyclic Coordinate Descent, rotates one joint at a time so the end of the chain points towards the target
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CcdSolver;
+
+impl IkSolver for CcdSolver {
+    fn iterate(&self, chain: &mut IkChain, target: Vec3) {
+        // Makes sure the chain is attached to the start before rotating around the joints
+        constrain_chain(chain, target);
+
+        let (effector, effector_target) = chain.effector(target);
+
+        for joint in (0..effector).rev() {
+            let pivot = chain.points[joint];
+            let to_end = chain.points[effector] - pivot;
+            let to_target = effector_target - pivot;
+
+            let (Some(to_end), Some(to_target)) =
+                (to_end.try_normalize(), to_target.try_normalize())
+            else {
+                continue;
+            };
+
+            let rotation = Quat::from_rotation_arc(to_end, to_target);
+            for point in chain.points[joint + 1..].iter_mut() {
+                *point = pivot + rotation * (*point - pivot);
+            }
+        }
+
+        constrain_chain(chain, target);
+    }
+}
@@ -0,0 +1,38 @@
+use glam::Vec3;
+
+use super::{constrain_chain, IkSolver};
+use crate::ik::IkChain;
+
+/// Forward And Backward Reaching Inverse Kinematics, moves the points along the chain instead of rotating joints
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FabrikSolver;
+
+impl IkSolver for FabrikSolver {
+    fn iterate(&self, chain: &mut IkChain, target: Vec3) {
+        backward_fabrik_pass(chain, target);
+        forward_fabrik_pass(chain, target);
+    }
+}
+
+/// Places each point from the start outwards, applying the joint constraints along the way
+pub fn forward_fabrik_pass(chain: &mut IkChain, target: Vec3) {
+    constrain_chain(chain, target);
+}
+
+/// Places each point from the target inwards
+pub fn backward_fabrik_pass(chain: &mut IkChain, target: Vec3) {
+    let points_count = chain.points.len();
+
+    chain.points[points_count - 1] = target;
+
+    // With an end orientation the last segment gets placed along it, so the effector is the start of that segment
+    let (effector, effector_target) = chain.effector(target);
+    chain.points[effector] = effector_target;
+
+    for i in (0..effector).rev() {
+        let segment = chain.segment(i);
+        let direction = (segment.start - segment.end).normalize_or_zero();
+
+        chain.points[i] = segment.end + direction * segment.length;
+    }
+}
@@ -0,0 +1,53 @@
+use glam::{Mat3, Quat, Vec3};
+
+use super::{constrain_chain, IkSolver};
+use crate::ik::IkChain;
+
+/// Damped least squares using the Jacobian of the end position, every joint is treated as a ball joint
+#[derive(Clone, Copy, Debug)]
+pub struct JacobianSolver {
+    /// Higher damping gives smaller but more stable steps, especially near singular poses like a straight chain
+    pub damping: f32,
+}
+
+impl Default for JacobianSolver {
+    fn default() -> Self {
+        JacobianSolver { damping: 0.5 }
+    }
+}
+
+impl IkSolver for JacobianSolver {
+    fn iterate(&self, chain: &mut IkChain, target: Vec3) {
+        constrain_chain(chain, target);
+
+        let (effector, effector_target) = chain.effector(target);
+        let end = chain.points[effector];
+        let error = effector_target - end;
+
+        // Each joint can rotate around the three world axes, the change in end position for a rotation around
+        // axis a is (a x r) with r the vector from the joint to the effector. Summing these columns gives J * J^T.
+        let mut jacobian_squared = Mat3::ZERO;
+        for joint in 0..effector {
+            let r = end - chain.points[joint];
+            jacobian_squared += Mat3::from_diagonal(Vec3::splat(r.length_squared()))
+                - Mat3::from_cols(r * r.x, r * r.y, r * r.z);
+        }
+
+        let damping_squared = Mat3::from_diagonal(Vec3::splat(self.damping * self.damping));
+        let weighted_error = (jacobian_squared + damping_squared).inverse() * error;
+
+        // J^T * weighted error gives a rotation vector of (r x weighted error) per joint.
+        // Going from the end inwards keeps the pivots of the remaining joints in place.
+        for joint in (0..effector).rev() {
+            let pivot = chain.points[joint];
+            let r = end - pivot;
+            let rotation = Quat::from_scaled_axis(r.cross(weighted_error));
+
+            for point in chain.points[joint + 1..].iter_mut() {
+                *point = pivot + rotation * (*point - pivot);
+            }
+        }
+
+        constrain_chain(chain, target);
+    }
+}
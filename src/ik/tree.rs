@@ -0,0 +1,268 @@
+use glam::Vec3;
+
+use super::{calculate_chain_lengths, IkError, SolveReport, SolveSettings};
+
+/// A branching IK skeleton, every branch is a chain that starts at the root or at the end of its parent branch.
+///
+/// Solved with multiple end effector FABRIK: branches with a target pull on their parent,
+/// and a junction shared by several branches ends up at the average of where each of them wants it.
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+pub struct IkTree {
+    /// Position the tree starts at, in world space
+    pub root: Vec3,
+    branches: Vec<IkBranch>,
+    pub settings: SolveSettings,
+    last_report: SolveReport,
+}
+
+pub struct IkBranch {
+    parent: Option<usize>,
+    /// The first point is where the branch is attached, it always matches the root or the end of the parent
+    points: Vec<Vec3>,
+    lengths: Vec<f32>,
+    target: Option<Vec3>,
+}
+
+impl IkBranch {
+    /// Index of the branch this one is attached to, None if it's attached to the root
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    pub fn points(&self) -> &[Vec3] {
+        &self.points
+    }
+
+    pub fn end(&self) -> Vec3 {
+        self.points[self.points.len() - 1]
+    }
+
+    pub fn target(&self) -> Option<Vec3> {
+        self.target
+    }
+
+    fn total_length(&self) -> f32 {
+        self.lengths.iter().sum()
+    }
+}
+
+impl IkTree {
+    pub fn new(root: Vec3) -> Self {
+        IkTree {
+            root,
+            branches: Vec::new(),
+            settings: SolveSettings::default(),
+            last_report: SolveReport::default(),
+        }
+    }
+
+    pub fn with_settings(mut self, settings: SolveSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Add a branch through the given points, attached to the end of the parent branch or to the root if there's no parent.
+    /// Returns the index of the new branch.
+    pub fn add_branch(
+        &mut self,
+        parent: Option<usize>,
+        points: Vec<Vec3>,
+    ) -> Result<usize, IkError> {
+        let attachment = match parent {
+            Some(index) => self.branch(index)?.end(),
+            None => self.root,
+        };
+
+        if points.is_empty() {
+            return Err(IkError::TooFewPoints { count: 1 });
+        }
+
+        if !points.iter().all(|point| point.is_finite()) {
+            return Err(IkError::NonFiniteInput);
+        }
+
+        let points: Vec<Vec3> = std::iter::once(attachment).chain(points).collect();
+        let lengths = calculate_chain_lengths(&points);
+        if let Some(index) = lengths.iter().position(|&length| length <= f32::EPSILON) {
+            return Err(IkError::DegenerateSegment { index });
+        }
+
+        self.branches.push(IkBranch {
+            parent,
+            points,
+            lengths,
+            target: None,
+        });
+
+        Ok(self.branches.len() - 1)
+    }
+
+    pub fn branch(&self, index: usize) -> Result<&IkBranch, IkError> {
+        self.branches.get(index).ok_or(IkError::IndexOutOfRange {
+            index,
+            len: self.branches.len(),
+        })
+    }
+
+    pub fn branches(&self) -> &[IkBranch] {
+        &self.branches
+    }
+
+    /// Set where the end of a branch should reach towards, branches without a target just follow their parent
+    pub fn set_target(&mut self, index: usize, target: Option<Vec3>) -> Result<(), IkError> {
+        let len = self.branches.len();
+        let branch = self
+            .branches
+            .get_mut(index)
+            .ok_or(IkError::IndexOutOfRange { index, len })?;
+
+        branch.target = target;
+        Ok(())
+    }
+
+    /// How the last solve of this tree went, the error and overshoot are those of the worst end effector
+    pub fn last_report(&self) -> SolveReport {
+        self.last_report
+    }
+
+    /// Iterate until every end effector is within tolerance of its target or the iteration cap is reached
+    pub fn solve(&mut self) -> Result<SolveReport, IkError> {
+        let targets_are_finite = self
+            .branches
+            .iter()
+            .filter_map(|branch| branch.target)
+            .all(|target| target.is_finite());
+
+        if !self.root.is_finite() || !targets_are_finite {
+            return Err(IkError::NonFiniteInput);
+        }
+
+        let active = self.active_branches();
+        let mut iterations = 0;
+
+        while iterations < self.settings.max_iterations && !self.is_solved() {
+            self.backward_pass(&active);
+            self.forward_pass();
+            iterations += 1;
+        }
+
+        let overshoot = self.overshoot();
+        self.last_report = SolveReport {
+            iterations,
+            error: self.error(),
+            reachable: overshoot <= 0.0,
+            overshoot,
+        };
+
+        Ok(self.last_report)
+    }
+
+    /// A branch is active if it or any branch attached to it has a target, inactive branches don't pull on their parent
+    fn active_branches(&self) -> Vec<bool> {
+        let mut active: Vec<bool> = self
+            .branches
+            .iter()
+            .map(|branch| branch.target.is_some())
+            .collect();
+
+        // Children always come after their parent, so going backwards passes activity up to the root
+        for index in (0..self.branches.len()).rev() {
+            if let (true, Some(parent)) = (active[index], self.branches[index].parent) {
+                active[parent] = true;
+            }
+        }
+
+        active
+    }
+
+    /// Places each active branch from its end inwards, starting with the outermost branches
+    fn backward_pass(&mut self, active: &[bool]) {
+        for index in (0..self.branches.len()).rev() {
+            if !active[index] {
+                continue;
+            }
+
+            // The end of this branch is pulled towards its own target and the starts of its active children
+            let mut pulled_towards = Vec3::ZERO;
+            let mut pull_count = 0;
+
+            if let Some(target) = self.branches[index].target {
+                pulled_towards += target;
+                pull_count += 1;
+            }
+
+            for (child, branch) in self.branches.iter().enumerate() {
+                if active[child] && branch.parent == Some(index) {
+                    pulled_towards += branch.points[0];
+                    pull_count += 1;
+                }
+            }
+
+            let branch = &mut self.branches[index];
+            let last_index = branch.points.len() - 1;
+
+            branch.points[last_index] = pulled_towards / pull_count as f32;
+            for i in (0..last_index).rev() {
+                let direction = (branch.points[i] - branch.points[i + 1]).normalize_or_zero();
+                branch.points[i] = branch.points[i + 1] + direction * branch.lengths[i];
+            }
+        }
+    }
+
+    /// Places each branch from its attachment outwards, starting at the root
+    fn forward_pass(&mut self) {
+        for index in 0..self.branches.len() {
+            let attachment = match self.branches[index].parent {
+                Some(parent) => self.branches[parent].end(),
+                None => self.root,
+            };
+
+            let branch = &mut self.branches[index];
+            branch.points[0] = attachment;
+            for i in 0..branch.lengths.len() {
+                let direction = (branch.points[i + 1] - branch.points[i]).normalize_or_zero();
+                branch.points[i + 1] = branch.points[i] + direction * branch.lengths[i];
+            }
+        }
+    }
+
+    /// Returns true if the tree is attached to the root and every end effector is within tolerance of its target
+    fn is_solved(&self) -> bool {
+        let tolerance = self.settings.tolerance;
+        let attached_to_root = self
+            .branches
+            .iter()
+            .filter(|branch| branch.parent.is_none())
+            .all(|branch| branch.points[0].distance(self.root) <= tolerance);
+
+        attached_to_root && self.error() <= tolerance
+    }
+
+    /// Largest distance between an end effector and its target
+    fn error(&self) -> f32 {
+        self.branches
+            .iter()
+            .filter_map(|branch| branch.target.map(|target| branch.end().distance(target)))
+            .fold(0.0, f32::max)
+    }
+
+    /// Largest distance a target is beyond the reach of the branches leading up to it
+    fn overshoot(&self) -> f32 {
+        let mut reach_from_root = vec![0.0; self.branches.len()];
+
+        for (index, branch) in self.branches.iter().enumerate() {
+            let parent_reach = branch.parent.map_or(0.0, |parent| reach_from_root[parent]);
+            reach_from_root[index] = parent_reach + branch.total_length();
+        }
+
+        self.branches
+            .iter()
+            .zip(reach_from_root)
+            .filter_map(|(branch, reach)| {
+                branch
+                    .target
+                    .map(|target| self.root.distance(target) - reach)
+            })
+            .fold(0.0, f32::max)
+    }
+}
@@ -0,0 +1,7 @@
+//! Inverse kinematics chains and solvers working on `glam` types,
+//! with an optional Bevy plugin layer behind the `bevy` feature.
+
+pub mod ik;
+#[cfg(feature = "bevy")]
+pub mod plugin;
+pub mod rotations;
@@ -1,13 +1,11 @@
 mod camera;
-pub mod ik;
 mod world;
 mod spider;
-mod rotations;
 
 use bevy::{prelude::*, window};
 use camera::CameraPlugin;
-use ik::IkPlugin;
 use spider::SpiderPlugin;
+use walking_ik_spider::plugin::IkPlugin;
 use world::WorldPlugin;
 
 fn main() {
@@ -0,0 +1,118 @@
+pub mod ground;
+pub mod leg;
+pub mod posture;
+pub mod terrain;
+
+use crate::{
+    ik::{tree::IkTree, ChainSpace, IkChain},
+    rotations,
+};
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use ground::GroundPlugin;
+use leg::IkLegPlugin;
+use posture::BodyPosturePlugin;
+
+const POINT_RADIUS: f32 = 0.3;
+const POINT_COLOR: Color = Color::PURPLE;
+const SEGMENT_COLOR: Color = Color::CYAN;
+
+const DRAW_CHAIN_GIZMOS: bool = false;
+const DRAW_ORIENTATION_GIZMOS: bool = false;
+
+pub struct IkPlugin;
+
+impl Plugin for IkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((IkLegPlugin, GroundPlugin, BodyPosturePlugin))
+            .add_systems(
+                Update,
+                (
+                    solve_ik_trees,
+                    draw_ik_chain_gizmos,
+                    draw_ik_tree_gizmos.after(solve_ik_trees),
+                ),
+            );
+    }
+}
+
+/// Returns the transform from the space of the chain to world space,
+/// where `global_transform` belongs to the entity holding the chain
+pub fn chain_to_world(chain: &IkChain, global_transform: Option<&GlobalTransform>) -> Affine3A {
+    match (chain.space, global_transform) {
+        (ChainSpace::Local, Some(global_transform)) => global_transform.affine(),
+        _ => Affine3A::IDENTITY,
+    }
+}
+
+/// Solves every IK tree towards the targets of its branches, trees are solved in parallel
+pub fn solve_ik_trees(mut ik_trees: Query<&mut IkTree>) {
+    ik_trees.par_iter_mut().for_each_mut(|mut tree| {
+        if let Err(error) = tree.solve() {
+            warn!("Couldn't solve IK tree: {}", error);
+        }
+    });
+}
+
+// Gizmos
+
+fn draw_ik_chain_gizmos(
+    mut gizmos: Gizmos,
+    ik_chains: Query<(&IkChain, Option<&GlobalTransform>)>,
+) {
+    if DRAW_CHAIN_GIZMOS {
+        for (chain, global_transform) in ik_chains.iter() {
+            let to_world = chain_to_world(chain, global_transform);
+
+            for point in chain.points().iter() {
+                let point = to_world.transform_point3(*point);
+                gizmos.sphere(point, Quat::IDENTITY, POINT_RADIUS, POINT_COLOR);
+            }
+
+            for segment in chain.points().windows(2) {
+                let start = to_world.transform_point3(segment[0]);
+                let end = to_world.transform_point3(segment[1]);
+                gizmos.line(start, end, SEGMENT_COLOR);
+            }
+        }
+    }
+
+    if DRAW_ORIENTATION_GIZMOS {
+        for (chain, global_transform) in ik_chains.iter() {
+            let to_world = chain_to_world(chain, global_transform);
+            let start = to_world.transform_point3(chain.start);
+            let end = to_world.transform_point3(chain.end());
+            let first_joint = to_world.transform_point3(chain.points()[1]);
+
+            let leg_orientation = rotations::looking_at(start, end, Vec3::Y);
+            let joint_orientation = rotations::looking_at(start, first_joint, Vec3::Y);
+
+            draw_orientation_gizmos(&mut gizmos, start, leg_orientation);
+            draw_orientation_gizmos(&mut gizmos, start, joint_orientation);
+        }
+    }
+}
+
+fn draw_ik_tree_gizmos(mut gizmos: Gizmos, ik_trees: Query<&IkTree>) {
+    if DRAW_CHAIN_GIZMOS {
+        for tree in ik_trees.iter() {
+            gizmos.sphere(tree.root, Quat::IDENTITY, POINT_RADIUS, POINT_COLOR);
+
+            for branch in tree.branches() {
+                for point in branch.points().iter().skip(1) {
+                    gizmos.sphere(*point, Quat::IDENTITY, POINT_RADIUS, POINT_COLOR);
+                }
+
+                for segment in branch.points().windows(2) {
+                    gizmos.line(segment[0], segment[1], SEGMENT_COLOR);
+                }
+            }
+        }
+    }
+}
+
+fn draw_orientation_gizmos(gizmos: &mut Gizmos, position: Vec3, orientation: Quat) {
+    gizmos.ray(position, orientation * Vec3::X, Color::GREEN);
+    gizmos.ray(position, orientation * Vec3::Y, Color::RED);
+    gizmos.ray(position, orientation * Vec3::Z, Color::BLUE);
+}
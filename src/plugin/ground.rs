@@ -0,0 +1,161 @@
+use bevy::{math::Affine3A, prelude::*};
+
+/// How far above a foot position the ground ray starts, so feet can step up onto things
+const STEP_UP_HEIGHT: f32 = 3.0;
+/// How far below a foot position the ground ray reaches, so feet can step down off things
+const STEP_DOWN_DEPTH: f32 = 3.0;
+
+pub struct GroundPlugin;
+
+impl Plugin for GroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ground>()
+            .add_systems(PreUpdate, update_ground_colliders);
+    }
+}
+
+/// Where a ray hit the ground
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroundHit {
+    pub point: Vec3,
+    /// Direction the surface faces at the hit point, normalized
+    pub normal: Vec3,
+    /// How far the ray travelled before hitting
+    pub distance: f32,
+}
+
+/// Anything feet can stand on
+pub trait GroundQuery: Send + Sync {
+    /// Casts a ray straight down from the origin, returns the first hit within `max_distance`
+    fn raycast_down(&self, origin: Vec3, max_distance: f32) -> Option<GroundHit>;
+
+    /// Returns where the ground is below or above the given position, if there's ground nearby
+    fn ground_near(&self, position: Vec3) -> Option<GroundHit> {
+        self.raycast_down(
+            position + Vec3::Y * STEP_UP_HEIGHT,
+            STEP_UP_HEIGHT + STEP_DOWN_DEPTH,
+        )
+    }
+
+    /// Returns the ground position below or above the given position, or the position itself if there's no ground nearby
+    fn place_on_ground(&self, position: Vec3) -> Vec3 {
+        self.ground_near(position).map_or(position, |hit| hit.point)
+    }
+}
+
+/// A box feet can stand on, the half extents are in the local space of the entity so it follows its transform
+#[derive(Component, Clone, Copy, Debug)]
+pub struct GroundCollider {
+    pub half_extents: Vec3,
+}
+
+impl GroundCollider {
+    pub fn new(size: Vec3) -> Self {
+        GroundCollider {
+            half_extents: size / 2.0,
+        }
+    }
+}
+
+/// Everything feet can stand on, the colliders in the world and any extra ground queries like terrain.
+/// This is a ground query itself, returning the closest hit of all of them.
+#[derive(Resource, Default)]
+pub struct Ground {
+    /// Box colliders with the transform from world space to their local space, refreshed every update
+    colliders: Vec<(Affine3A, GroundCollider)>,
+    queries: Vec<Box<dyn GroundQuery>>,
+}
+
+impl Ground {
+    /// Add a surface that isn't made of colliders, like a heightmap
+    pub fn add_query(&mut self, query: impl GroundQuery + 'static) {
+        self.queries.push(Box::new(query));
+    }
+}
+
+impl GroundQuery for Ground {
+    fn raycast_down(&self, origin: Vec3, max_distance: f32) -> Option<GroundHit> {
+        let collider_hits = self
+            .colliders
+            .iter()
+            .filter_map(|(world_to_local, collider)| {
+                raycast_box_down(*world_to_local, collider, origin, max_distance)
+            });
+        let query_hits = self
+            .queries
+            .iter()
+            .filter_map(|query| query.raycast_down(origin, max_distance));
+
+        collider_hits
+            .chain(query_hits)
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+}
+
+/// Slab test of a downwards ray against a box in its local space
+fn raycast_box_down(
+    world_to_local: Affine3A,
+    collider: &GroundCollider,
+    origin: Vec3,
+    max_distance: f32,
+) -> Option<GroundHit> {
+    // The direction isn't normalized in local space, so distances along the ray stay the same as in world space
+    let local_origin = world_to_local.transform_point3(origin);
+    let local_direction = world_to_local.transform_vector3(Vec3::NEG_Y);
+
+    let mut near = 0.0;
+    let mut far = max_distance;
+    let mut local_normal = Vec3::ZERO;
+
+    for axis in 0..3 {
+        let extent = collider.half_extents[axis];
+        let (origin, direction) = (local_origin[axis], local_direction[axis]);
+
+        if direction.abs() <= f32::EPSILON {
+            if origin.abs() > extent {
+                return None;
+            }
+            continue;
+        }
+
+        let first = (-extent - origin) / direction;
+        let second = (extent - origin) / direction;
+        let (entry, exit) = (first.min(second), first.max(second));
+
+        if entry > near {
+            near = entry;
+            // The ray enters through the face that points back at it
+            local_normal = Vec3::ZERO;
+            local_normal[axis] = -direction.signum();
+        }
+        far = far.min(exit);
+
+        if near > far {
+            return None;
+        }
+    }
+
+    // Rays starting inside the box don't hit it
+    if local_normal == Vec3::ZERO {
+        return None;
+    }
+
+    // Normals go back to world space with the inverse transpose of the local to world transform
+    let normal = world_to_local.matrix3.transpose() * local_normal;
+    Some(GroundHit {
+        point: origin + Vec3::NEG_Y * near,
+        normal: normal.normalize_or_zero(),
+        distance: near,
+    })
+}
+
+/// Copies the colliders and their transforms into the ground resource
+pub fn update_ground_colliders(
+    mut ground: ResMut<Ground>,
+    colliders: Query<(&GroundCollider, &GlobalTransform)>,
+) {
+    ground.colliders = colliders
+        .iter()
+        .map(|(collider, global_transform)| (global_transform.affine().inverse(), *collider))
+        .collect();
+}
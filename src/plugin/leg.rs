@@ -0,0 +1,440 @@
+pub mod trajectory;
+
+use bevy::{math::Affine3A, prelude::*};
+
+use super::{
+    chain_to_world,
+    ground::{update_ground_colliders, Ground, GroundQuery},
+};
+use crate::ik::{
+    solver::{ChainSolver, IkSolver},
+    IkChain,
+};
+use trajectory::StepTrajectory;
+
+const TARGET_RADIUS: f32 = 0.7;
+const TARGET_COLOR: Color = Color::ORANGE_RED;
+const CURRENT_TARGET_COLOR: Color = Color::LIME_GREEN;
+
+/// Fractions of a step at which the foot is done lifting off, and at which it starts landing
+const LIFT_END_FRACTION: f32 = 0.2;
+const LANDING_START_FRACTION: f32 = 0.8;
+
+/// Most steps a leg remembers between phase updates, older ones are dropped without being reported
+const MAX_PENDING_STEPS: usize = 4;
+
+const DRAW_TARGET_GIZMOS: bool = false;
+
+pub struct IkLegPlugin;
+
+impl Plugin for IkLegPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LegStepStarted>()
+            .add_event::<LegStepLanded>()
+            .add_systems(
+                PreUpdate,
+                place_new_legs_on_ground.after(update_ground_colliders),
+            )
+            .add_systems(
+                Update,
+                (
+                    draw_animated_leg_gizmos,
+                    animate_leg_towards_target,
+                    update_leg_phases.after(animate_leg_towards_target),
+                ),
+            );
+    }
+}
+
+/// A leg that steps its foot from target to target and solves its chain towards the foot.
+/// Needs `animate_leg_towards_target` to move and `update_leg_phases` to track its phase and report its steps,
+/// `IkLegPlugin` adds both.
+#[derive(Component)]
+pub struct AnimatedLeg {
+    /// Offset from the start of the chain to the ideal foot position, in the space of the chain
+    pub reposition_target_offset: Vec3,
+    pub previous_target: Vec3,
+    pub current_target: Vec3,
+    pub lerp_fraction: f32,
+    pub timing: StepTiming,
+    /// How many seconds the current step takes
+    step_duration: f32,
+    /// Velocity of whatever the leg is attached to, measured from the movement of the leg entity
+    body_velocity: Vec3,
+    last_body_position: Option<Vec3>,
+    phase: LegPhase,
+    phase_started_at: f32,
+    /// Where each step started since the last phase update goes from and to, until their start is reported.
+    /// A leg can be given several targets between updates, the last `MAX_PENDING_STEPS` of them get reported.
+    pending_steps: Vec<(Vec3, Vec3)>,
+}
+
+impl AnimatedLeg {
+    pub fn new(reposition_target_offset: Vec3, position: Vec3) -> Self {
+        AnimatedLeg {
+            reposition_target_offset,
+            previous_target: position,
+            current_target: position,
+            // Legs start out planted on their position instead of stepping onto it
+            lerp_fraction: 1.0,
+            timing: StepTiming::default(),
+            step_duration: StepTiming::default().min_duration,
+            body_velocity: Vec3::ZERO,
+            last_body_position: None,
+            phase: LegPhase::Planted,
+            phase_started_at: 0.0,
+            pending_steps: Vec::new(),
+        }
+    }
+
+    pub fn with_timing(mut self, timing: StepTiming) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    fn increase_lerp_fraction(&mut self, delta_seconds: f32) {
+        self.lerp_fraction = (self.lerp_fraction + delta_seconds / self.step_duration).min(1.0);
+    }
+
+    /// How many seconds the current or last step takes from lifting off to landing
+    pub fn step_duration(&self) -> f32 {
+        self.step_duration
+    }
+
+    /// Velocity of whatever the leg is attached to, as of the last animation update
+    pub fn body_velocity(&self) -> Vec3 {
+        self.body_velocity
+    }
+
+    /// Updates the body velocity from where the leg entity is now and where it was last update
+    fn track_body_position(&mut self, position: Vec3, delta_seconds: f32) {
+        if let Some(last_position) = self.last_body_position.filter(|_| delta_seconds > 0.0) {
+            self.body_velocity = (position - last_position) / delta_seconds;
+        }
+
+        self.last_body_position = Some(position);
+    }
+
+    /// Where the leg is in its step, as of the last animation update
+    pub fn phase(&self) -> LegPhase {
+        self.phase
+    }
+
+    /// Time in seconds since startup at which the leg entered its current phase
+    pub fn phase_started_at(&self) -> f32 {
+        self.phase_started_at
+    }
+
+    /// How many seconds the leg has been in its current phase, given the time since startup
+    pub fn time_in_phase(&self, elapsed_seconds: f32) -> f32 {
+        elapsed_seconds - self.phase_started_at
+    }
+
+    /// Moves the leg into the phase matching its step progress, a new step always restarts the phase
+    fn update_phase(&mut self, elapsed_seconds: f32) {
+        let phase = LegPhase::at_step_fraction(self.lerp_fraction);
+        if phase != self.phase || !self.pending_steps.is_empty() {
+            self.phase = phase;
+            self.phase_started_at = elapsed_seconds;
+        }
+    }
+
+    /// Returns where the foot would ideally be, in world space
+    pub fn ideal_target(&self, chain: &IkChain, chain_to_world: Affine3A) -> Vec3 {
+        chain_to_world.transform_point3(chain.start + self.reposition_target_offset)
+    }
+
+    /// Returns the step the foot should take next. The ideal position keeps moving with the body while the foot is in the air,
+    /// so the target looks ahead by how long the step takes, and the step is given that same duration to land in front of the body.
+    pub fn predicted_step(&self, chain: &IkChain, chain_to_world: Affine3A) -> StepPlan {
+        let ideal_target = self.ideal_target(chain, chain_to_world);
+        let duration = self.timing.step_duration(
+            self.current_target.distance(ideal_target),
+            self.body_velocity.length(),
+        );
+
+        StepPlan {
+            target: ideal_target + self.body_velocity * duration,
+            duration,
+            ground_normal: None,
+        }
+    }
+
+    /// Returns the predicted step with its target moved onto the ground, it stays where it is if there's no ground nearby
+    pub fn grounded_step(
+        &self,
+        chain: &IkChain,
+        chain_to_world: Affine3A,
+        ground: &dyn GroundQuery,
+    ) -> StepPlan {
+        let step = self.predicted_step(chain, chain_to_world);
+
+        match ground.ground_near(step.target) {
+            Some(hit) => StepPlan {
+                target: hit.point,
+                ground_normal: Some(hit.normal),
+                ..step
+            },
+            None => step,
+        }
+    }
+
+    /// Returns true while the leg is moving towards a new target
+    pub fn is_stepping(&self) -> bool {
+        self.lerp_fraction < 1.0
+    }
+
+    /// Start a step towards the target, how long it takes depends on the step distance and how fast the body moves
+    pub fn set_new_target(&mut self, target: Vec3) {
+        let duration = self.timing.step_duration(
+            self.current_target.distance(target),
+            self.body_velocity.length(),
+        );
+
+        self.start_step(StepPlan {
+            target,
+            duration,
+            ground_normal: None,
+        });
+    }
+
+    /// Start a step that was planned ahead, like one from `predicted_step`
+    pub fn start_step(&mut self, step: StepPlan) {
+        self.previous_target = self.current_target;
+        self.current_target = step.target;
+        self.step_duration = step.duration;
+        self.lerp_fraction = 0.0;
+
+        // Without phase updates nothing reports the steps, so don't let them pile up
+        if self.pending_steps.len() == MAX_PENDING_STEPS {
+            self.pending_steps.remove(0);
+        }
+        self.pending_steps
+            .push((self.previous_target, self.current_target));
+    }
+}
+
+/// Where a step goes and how long it takes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepPlan {
+    /// Where the foot lands, in world space
+    pub target: Vec3,
+    /// How many seconds the step takes
+    pub duration: f32,
+    /// Direction the ground faces where the foot lands, in world space, `None` if it doesn't land on known ground
+    pub ground_normal: Option<Vec3>,
+}
+
+/// Controls how long steps take
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepTiming {
+    /// Speed of the foot relative to the body in units per second, the body speed is added on top so feet keep up
+    pub foot_speed: f32,
+    /// Shortest and longest a step can take in seconds
+    pub min_duration: f32,
+    pub max_duration: f32,
+}
+
+impl StepTiming {
+    /// Returns how many seconds a step of the given distance takes while the body moves at the given speed
+    pub fn step_duration(&self, distance: f32, body_speed: f32) -> f32 {
+        let foot_speed = self.foot_speed + body_speed;
+        let duration = if foot_speed > 0.0 {
+            distance / foot_speed
+        } else {
+            self.max_duration
+        };
+
+        duration.clamp(self.min_duration, self.max_duration)
+    }
+}
+
+impl Default for StepTiming {
+    fn default() -> Self {
+        StepTiming {
+            foot_speed: 12.0,
+            min_duration: 0.1,
+            max_duration: 0.4,
+        }
+    }
+}
+
+/// Sent when a leg gets a new target and starts stepping towards it
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LegStepStarted {
+    pub leg: Entity,
+    /// The entity the leg is attached to, like the spider a spider leg belongs to
+    pub body: Option<Entity>,
+    /// Where the foot steps from and to, in world space
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+/// Sent when a leg finishes its step and its foot is planted again
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LegStepLanded {
+    pub leg: Entity,
+    /// The entity the leg is attached to, like the spider a spider leg belongs to
+    pub body: Option<Entity>,
+    /// Where the foot stepped from and to, in world space
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+/// Where a foot is in its step cycle
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LegPhase {
+    /// The foot is on the ground and stays where it is
+    #[default]
+    Planted,
+    /// The foot leaves the ground at the start of a step
+    Lifting,
+    /// The foot moves through the air towards its new target
+    Swinging,
+    /// The foot comes down onto its new target at the end of a step
+    Landing,
+}
+
+impl LegPhase {
+    /// Returns the phase of a step that is the given fraction of the way done
+    pub fn at_step_fraction(fraction: f32) -> Self {
+        if fraction >= 1.0 {
+            LegPhase::Planted
+        } else if fraction < LIFT_END_FRACTION {
+            LegPhase::Lifting
+        } else if fraction < LANDING_START_FRACTION {
+            LegPhase::Swinging
+        } else {
+            LegPhase::Landing
+        }
+    }
+
+    /// Returns true if the foot is carrying weight, only planted feet do
+    pub fn is_on_ground(&self) -> bool {
+        *self == LegPhase::Planted
+    }
+}
+
+/// Moves the feet of newly spawned legs onto the ground, later targets are placed with `AnimatedLeg::grounded_step`.
+/// This runs after the colliders are copied into the ground, so it also sees ground spawned in the same frame as the legs.
+pub fn place_new_legs_on_ground(
+    mut new_legs: Query<&mut AnimatedLeg, Added<AnimatedLeg>>,
+    ground: Res<Ground>,
+) {
+    for mut leg in new_legs.iter_mut() {
+        let position = ground.place_on_ground(leg.current_target);
+        leg.previous_target = position;
+        leg.current_target = position;
+    }
+}
+
+/// Moves every animated leg along its step and solves its chain, legs are solved in parallel
+#[allow(clippy::type_complexity)]
+pub fn animate_leg_towards_target(
+    mut animated_legs: Query<(
+        &mut IkChain,
+        &mut AnimatedLeg,
+        Option<&ChainSolver>,
+        Option<&StepTrajectory>,
+        Option<&GlobalTransform>,
+    )>,
+    time: Res<Time>,
+) {
+    let delta_seconds = time.delta_seconds();
+
+    animated_legs.par_iter_mut().for_each_mut(
+        |(mut chain, mut leg, solver, trajectory, global_transform)| {
+            if let Some(global_transform) = global_transform {
+                leg.track_body_position(global_transform.translation(), delta_seconds);
+            }
+
+            leg.increase_lerp_fraction(delta_seconds);
+
+            let trajectory = trajectory.unwrap_or(&StepTrajectory::DEFAULT);
+            let interpolated_target =
+                trajectory.position(leg.previous_target, leg.current_target, leg.lerp_fraction);
+
+            // Leg targets are in world space, the chain might not be
+            let to_chain = chain_to_world(&chain, global_transform).inverse();
+            let interpolated_target = to_chain.transform_point3(interpolated_target);
+
+            let solver = solver.copied().unwrap_or_default();
+            if let Err(error) = solver.solve(&mut chain, interpolated_target) {
+                warn!("Couldn't solve leg chain: {}", error);
+            }
+        },
+    );
+}
+
+/// Moves every animated leg into the phase matching its step and sends the step events.
+/// This runs after the legs are animated, on its own so the events go out in a consistent order.
+pub fn update_leg_phases(
+    mut animated_legs: Query<(Entity, &mut AnimatedLeg, Option<&Parent>)>,
+    mut steps_started: EventWriter<LegStepStarted>,
+    mut steps_landed: EventWriter<LegStepLanded>,
+    time: Res<Time>,
+) {
+    let elapsed_seconds = time.elapsed_seconds();
+
+    for (entity, mut leg, parent) in animated_legs.iter_mut() {
+        let previous_phase = leg.phase;
+        let step_started = !leg.pending_steps.is_empty();
+
+        leg.update_phase(elapsed_seconds);
+
+        let body = parent.map(|parent| parent.get());
+        for (start, end) in leg.pending_steps.drain(..) {
+            steps_started.send(LegStepStarted {
+                leg: entity,
+                body,
+                start,
+                end,
+            });
+        }
+
+        let (start, end) = (leg.previous_target, leg.current_target);
+
+        // A step can start and finish within one update, so a planted leg can land again
+        let landed = previous_phase != LegPhase::Planted || step_started;
+        if landed && leg.phase == LegPhase::Planted {
+            steps_landed.send(LegStepLanded {
+                leg: entity,
+                body,
+                start,
+                end,
+            });
+        }
+    }
+}
+
+// Gizmos
+
+fn draw_animated_leg_gizmos(
+    mut gizmos: Gizmos,
+    animated_legs: Query<(&IkChain, &AnimatedLeg, Option<&GlobalTransform>)>,
+) {
+    if DRAW_TARGET_GIZMOS {
+        for (chain, leg, global_transform) in animated_legs.iter() {
+            draw_target(
+                &mut gizmos,
+                leg.ideal_target(chain, chain_to_world(chain, global_transform)),
+                TARGET_RADIUS,
+                TARGET_COLOR,
+            );
+            draw_target(
+                &mut gizmos,
+                leg.current_target,
+                TARGET_RADIUS,
+                CURRENT_TARGET_COLOR,
+            );
+        }
+    }
+}
+
+fn draw_target(gizmos: &mut Gizmos, position: Vec3, radius: f32, color: Color) {
+    // Inner
+    gizmos.circle(position, Vec3::Y, 0.1, color);
+
+    // Outer
+    gizmos.circle(position, Vec3::Y, radius, color);
+}
@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+
+/// Height of the default step arc, relative to the distance of the step
+const DEFAULT_HEIGHT: f32 = 0.7;
+
+/// The path a foot follows through the air during a step, legs without this component use `StepTrajectory::DEFAULT`
+#[derive(Component, Clone, Debug)]
+pub struct StepTrajectory {
+    pub curve: StepCurve,
+    /// Maps the time spent stepping onto the progress along the curve
+    pub easing: Easing,
+}
+
+impl StepTrajectory {
+    /// A quadratic Bézier arc at a constant pace
+    pub const DEFAULT: StepTrajectory = StepTrajectory {
+        curve: StepCurve::QuadraticBezier {
+            height: DEFAULT_HEIGHT,
+        },
+        easing: Easing::Linear,
+    };
+
+    pub fn new(curve: StepCurve, easing: Easing) -> Self {
+        StepTrajectory { curve, easing }
+    }
+
+    /// High and fast steps that slow down just before landing
+    pub fn skittish() -> Self {
+        StepTrajectory::new(StepCurve::QuadraticBezier { height: 1.2 }, Easing::EaseOut)
+    }
+
+    /// Low steps that take a while to get going and to settle
+    pub fn heavy() -> Self {
+        StepTrajectory::new(
+            StepCurve::CubicBezier {
+                lift_height: 0.4,
+                land_height: 0.1,
+            },
+            Easing::EaseInOut,
+        )
+    }
+
+    /// Straight up, across and straight down again
+    pub fn mechanical() -> Self {
+        let keyframes = vec![
+            StepKeyframe::new(0.0, 0.0),
+            StepKeyframe::new(0.2, 0.4),
+            StepKeyframe::new(0.8, 0.4),
+            StepKeyframe::new(1.0, 0.0),
+        ];
+
+        StepTrajectory::new(StepCurve::Keyframed(keyframes), Easing::Linear)
+    }
+
+    /// Returns where the foot is after the given fraction of the step time, from 0 at the start to 1 at the end
+    pub fn position(&self, start: Vec3, end: Vec3, fraction: f32) -> Vec3 {
+        self.curve
+            .position(start, end, self.easing.apply(fraction.clamp(0.0, 1.0)))
+    }
+}
+
+impl Default for StepTrajectory {
+    fn default() -> Self {
+        StepTrajectory::DEFAULT
+    }
+}
+
+/// Shape of a step, heights are relative to the distance of the step so longer steps arc higher
+#[derive(Clone, Debug, PartialEq)]
+pub enum StepCurve {
+    /// Arcs through a control point above the middle of the step
+    QuadraticBezier { height: f32 },
+    /// Lifts off towards a control point above the start and comes down from one above the end
+    CubicBezier { lift_height: f32, land_height: f32 },
+    /// Moves in a straight line with half a sine wave on top
+    SineArc { height: f32 },
+    /// Moves in a straight line with the height interpolated between keyframes, which should be sorted by fraction
+    Keyframed(Vec<StepKeyframe>),
+}
+
+impl StepCurve {
+    /// Returns the point at the given progress along the curve, from 0 at the start to 1 at the end
+    pub fn position(&self, start: Vec3, end: Vec3, progress: f32) -> Vec3 {
+        let distance = start.distance(end);
+
+        match self {
+            StepCurve::QuadraticBezier { height } => {
+                let anchor = start.lerp(end, 0.5) + Vec3::Y * distance * *height;
+
+                let start_to_anchor = start.lerp(anchor, progress);
+                let anchor_to_end = anchor.lerp(end, progress);
+                start_to_anchor.lerp(anchor_to_end, progress)
+            }
+            StepCurve::CubicBezier {
+                lift_height,
+                land_height,
+            } => {
+                let lift_anchor = start + Vec3::Y * distance * *lift_height;
+                let land_anchor = end + Vec3::Y * distance * *land_height;
+
+                let a = start.lerp(lift_anchor, progress);
+                let b = lift_anchor.lerp(land_anchor, progress);
+                let c = land_anchor.lerp(end, progress);
+                a.lerp(b, progress).lerp(b.lerp(c, progress), progress)
+            }
+            StepCurve::SineArc { height } => {
+                let arc = (progress * std::f32::consts::PI).sin();
+                start.lerp(end, progress) + Vec3::Y * distance * *height * arc
+            }
+            StepCurve::Keyframed(keyframes) => {
+                start.lerp(end, progress)
+                    + Vec3::Y * distance * keyframed_height(keyframes, progress)
+            }
+        }
+    }
+}
+
+/// Height of a keyframed step curve at a point in the step
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepKeyframe {
+    /// How far along the step this keyframe is, from 0 to 1
+    pub fraction: f32,
+    /// Height relative to the distance of the step
+    pub height: f32,
+}
+
+impl StepKeyframe {
+    pub fn new(fraction: f32, height: f32) -> Self {
+        StepKeyframe { fraction, height }
+    }
+}
+
+/// Linearly interpolates between the keyframes around the progress, holding the first and last height outside of them
+fn keyframed_height(keyframes: &[StepKeyframe], progress: f32) -> f32 {
+    let next_index = keyframes
+        .iter()
+        .position(|keyframe| keyframe.fraction > progress);
+
+    match next_index {
+        None => keyframes.last().map_or(0.0, |keyframe| keyframe.height),
+        Some(0) => keyframes[0].height,
+        Some(index) => {
+            let previous = keyframes[index - 1];
+            let next = keyframes[index];
+            let fraction = (progress - previous.fraction) / (next.fraction - previous.fraction);
+
+            previous.height + (next.height - previous.height) * fraction
+        }
+    }
+}
+
+/// Timing of a step, maps a fraction of the step time onto the progress along the step
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slow and speeds up
+    EaseIn,
+    /// Starts fast and slows down
+    EaseOut,
+    /// Starts and ends slow
+    EaseInOut,
+    /// Any function that maps 0 to 0 and 1 to 1
+    Custom(fn(f32) -> f32),
+}
+
+impl Easing {
+    pub fn apply(&self, fraction: f32) -> f32 {
+        match self {
+            Easing::Linear => fraction,
+            Easing::EaseIn => fraction * fraction,
+            Easing::EaseOut => 1.0 - (1.0 - fraction) * (1.0 - fraction),
+            Easing::EaseInOut => fraction * fraction * (3.0 - 2.0 * fraction),
+            Easing::Custom(function) => function(fraction),
+        }
+    }
+}
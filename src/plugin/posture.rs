@@ -0,0 +1,138 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::prelude::*;
+
+use super::leg::{animate_leg_towards_target, AnimatedLeg};
+use crate::rotations;
+
+/// How spread out feet have to be across their widest direction, compared to along it,
+/// for them to tell which way the ground tilts
+const MIN_FOOT_SPREAD_RATIO: f32 = 1e-3;
+
+pub struct BodyPosturePlugin;
+
+impl Plugin for BodyPosturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            adapt_body_to_planted_feet.before(animate_leg_towards_target),
+        );
+    }
+}
+
+/// Keeps a body at a height above its planted feet and tilts it along with the ground they stand on.
+/// The body is expected to be a top level entity with its legs as children, it keeps the heading it's steered in.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct BodyPosture {
+    /// Height of the body above the ground below it, measured straight up
+    pub clearance: f32,
+    /// How much of the tilt of the ground the body follows, 0 stays upright and 1 lies parallel to the ground
+    pub tilt_factor: f32,
+    /// Largest angle in radians the body tilts away from upright
+    pub max_tilt: f32,
+    /// How quickly the body moves towards its posture, higher is faster
+    pub smoothing: f32,
+}
+
+impl BodyPosture {
+    pub fn new(clearance: f32) -> Self {
+        BodyPosture {
+            clearance,
+            tilt_factor: 1.0,
+            max_tilt: FRAC_PI_4,
+            smoothing: 8.0,
+        }
+    }
+
+    /// Returns the height and rotation the body should have on the given ground,
+    /// keeping the heading of the current rotation
+    pub fn target(&self, ground: &FootPlane, position: Vec3, rotation: Quat) -> (f32, Quat) {
+        let height = ground.height_at(position.x, position.z) + self.clearance;
+
+        let up = Vec3::Y
+            .lerp(ground.normal, self.tilt_factor)
+            .normalize_or_zero();
+        let up = rotations::clamp_to_cone(up, Vec3::Y, self.max_tilt);
+        let (_, heading) = rotations::swing_twist(rotation, Vec3::Y);
+
+        (height, Quat::from_rotation_arc(Vec3::Y, up) * heading)
+    }
+}
+
+/// A plane fitted through the positions of planted feet
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FootPlane {
+    /// Average position of the feet
+    pub center: Vec3,
+    /// Direction the plane faces, normalized and always pointing up
+    pub normal: Vec3,
+}
+
+impl FootPlane {
+    /// Fits a plane through the feet with least squares on their heights,
+    /// returns `None` for fewer than three feet or feet that are all in a line
+    pub fn fit(feet: &[Vec3]) -> Option<Self> {
+        if feet.len() < 3 {
+            return None;
+        }
+
+        let center = feet.iter().sum::<Vec3>() / feet.len() as f32;
+
+        let (mut xx, mut xz, mut zz, mut xy, mut zy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for foot in feet {
+            let offset = *foot - center;
+            xx += offset.x * offset.x;
+            xz += offset.x * offset.z;
+            zz += offset.z * offset.z;
+            xy += offset.x * offset.y;
+            zy += offset.z * offset.y;
+        }
+
+        // The feet in a line leave the slope across that line undecided
+        let determinant = xx * zz - xz * xz;
+        if determinant <= MIN_FOOT_SPREAD_RATIO * (xx + zz).powi(2) {
+            return None;
+        }
+
+        let x_slope = (xy * zz - zy * xz) / determinant;
+        let z_slope = (zy * xx - xy * xz) / determinant;
+
+        Some(FootPlane {
+            center,
+            normal: Vec3::new(-x_slope, 1.0, -z_slope).normalize(),
+        })
+    }
+
+    /// Returns the height of the plane at the given horizontal position
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let offset = Vec3::new(x - self.center.x, 0.0, z - self.center.z);
+        self.center.y - offset.dot(self.normal) / self.normal.y
+    }
+}
+
+/// Moves bodies towards the height and tilt that fit their planted feet, bodies with too few planted feet keep their posture
+pub fn adapt_body_to_planted_feet(
+    mut bodies: Query<(&mut Transform, &BodyPosture, &Children)>,
+    legs: Query<&AnimatedLeg>,
+    time: Res<Time>,
+) {
+    for (mut transform, posture, children) in bodies.iter_mut() {
+        let planted_feet: Vec<Vec3> = legs
+            .iter_many(children)
+            // A leg that was just given a new target is still planted until its step starts, but its target is already the new one
+            .filter(|leg| leg.phase().is_on_ground() && !leg.is_stepping())
+            .map(|leg| leg.current_target)
+            .collect();
+
+        let Some(ground) = FootPlane::fit(&planted_feet) else {
+            continue;
+        };
+
+        let (height, rotation) = posture.target(&ground, transform.translation, transform.rotation);
+
+        // Frame rate independent smoothing, the same fraction of the way is covered every second
+        let fraction = 1.0 - (-posture.smoothing * time.delta_seconds()).exp();
+        transform.translation.y += (height - transform.translation.y) * fraction;
+        transform.rotation = transform.rotation.slerp(rotation, fraction);
+    }
+}
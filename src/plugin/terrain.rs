@@ -0,0 +1,242 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+
+use super::ground::{GroundHit, GroundQuery};
+
+/// How the terrain is generated, the same settings always give the same terrain
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainSettings {
+    pub seed: u32,
+    /// Width and depth of the square terrain, which is centered on the origin
+    pub size: f32,
+    /// Number of cells along each side of the heightmap
+    pub resolution: u32,
+    /// Highest the terrain goes above or below zero
+    pub amplitude: f32,
+    /// Width of the largest hills, smaller bumps are added on top of them
+    pub feature_size: f32,
+    /// Number of noise layers, each one half the width and height of the one before
+    pub octaves: u32,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        TerrainSettings {
+            seed: 0,
+            size: 200.0,
+            resolution: 200,
+            amplitude: 2.0,
+            feature_size: 24.0,
+            octaves: 4,
+        }
+    }
+}
+
+/// A heightmap generated from noise.
+/// Heights between the grid points follow the triangles of the mesh, so feet land on what is drawn.
+#[derive(Clone, Debug)]
+pub struct Terrain {
+    settings: TerrainSettings,
+    /// Heights of the grid points, row by row along the x axis
+    heights: Vec<f32>,
+}
+
+impl Terrain {
+    pub fn new(settings: TerrainSettings) -> Self {
+        let settings = TerrainSettings {
+            resolution: settings.resolution.max(1),
+            ..settings
+        };
+        let row_length = settings.resolution + 1;
+
+        let heights = (0..row_length * row_length)
+            .map(|index| {
+                let position = grid_position(&settings, index % row_length, index / row_length);
+                fractal_noise(&settings, position) * settings.amplitude
+            })
+            .collect();
+
+        Terrain { settings, heights }
+    }
+
+    pub fn settings(&self) -> &TerrainSettings {
+        &self.settings
+    }
+
+    /// Returns the height of the grid point in the given column and row
+    pub fn grid_height(&self, column: u32, row: u32) -> f32 {
+        self.heights[(row * (self.settings.resolution + 1) + column) as usize]
+    }
+
+    /// Returns the height of the terrain below the given position, or `None` if it's off the terrain
+    pub fn height_at(&self, x: f32, z: f32) -> Option<f32> {
+        self.triangle_at(x, z)
+            .map(|(origin, x_slope, z_slope)| origin + x_slope * x + z_slope * z)
+    }
+
+    /// Returns the direction the terrain faces below the given position, or `None` if it's off the terrain
+    pub fn normal_at(&self, x: f32, z: f32) -> Option<Vec3> {
+        self.triangle_at(x, z)
+            .map(|(_, x_slope, z_slope)| Vec3::new(-x_slope, 1.0, -z_slope).normalize())
+    }
+
+    /// Returns the plane of the triangle below the given position as a height at the origin and slopes along x and z
+    fn triangle_at(&self, x: f32, z: f32) -> Option<(f32, f32, f32)> {
+        let resolution = self.settings.resolution;
+        let cell_size = self.settings.size / resolution as f32;
+        let column = (x + self.settings.size / 2.0) / cell_size;
+        let row = (z + self.settings.size / 2.0) / cell_size;
+
+        let range = 0.0..=resolution as f32;
+        if !range.contains(&column) || !range.contains(&row) {
+            return None;
+        }
+
+        let cell_column = (column as u32).min(resolution - 1);
+        let cell_row = (row as u32).min(resolution - 1);
+        let fraction_x = column - cell_column as f32;
+        let fraction_z = row - cell_row as f32;
+
+        // Cells are split into two triangles along the diagonal from their first to their last corner
+        let first = self.grid_height(cell_column, cell_row);
+        let last = self.grid_height(cell_column + 1, cell_row + 1);
+        let (x_slope, z_slope) = if fraction_x >= fraction_z {
+            let next_column = self.grid_height(cell_column + 1, cell_row);
+            (next_column - first, last - next_column)
+        } else {
+            let next_row = self.grid_height(cell_column, cell_row + 1);
+            (last - next_row, next_row - first)
+        };
+
+        let (x_slope, z_slope) = (x_slope / cell_size, z_slope / cell_size);
+        let corner = grid_position(&self.settings, cell_column, cell_row);
+        Some((
+            first - x_slope * corner.x - z_slope * corner.y,
+            x_slope,
+            z_slope,
+        ))
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        let resolution = self.settings.resolution;
+        let row_length = resolution + 1;
+        let cell_size = self.settings.size / resolution as f32;
+
+        let mut positions = Vec::with_capacity(self.heights.len());
+        let mut normals = Vec::with_capacity(self.heights.len());
+        let mut uvs = Vec::with_capacity(self.heights.len());
+
+        for row in 0..row_length {
+            for column in 0..row_length {
+                let position = grid_position(&self.settings, column, row);
+                positions.push([position.x, self.grid_height(column, row), position.y]);
+
+                // Slopes towards the neighbouring grid points, clamped at the edges
+                let x_slope = self.grid_height((column + 1).min(resolution), row)
+                    - self.grid_height(column.saturating_sub(1), row);
+                let z_slope = self.grid_height(column, (row + 1).min(resolution))
+                    - self.grid_height(column, row.saturating_sub(1));
+                let normal = Vec3::new(-x_slope, 2.0 * cell_size, -z_slope).normalize();
+                normals.push(normal.to_array());
+
+                uvs.push([
+                    column as f32 / resolution as f32,
+                    row as f32 / resolution as f32,
+                ]);
+            }
+        }
+
+        let mut indices = Vec::with_capacity((resolution * resolution * 6) as usize);
+        for row in 0..resolution {
+            for column in 0..resolution {
+                let first = row * row_length + column;
+                let next_column = first + 1;
+                let next_row = first + row_length;
+                let last = next_row + 1;
+
+                indices.extend([first, last, next_column, first, next_row, last]);
+            }
+        }
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh
+    }
+}
+
+impl GroundQuery for Terrain {
+    fn raycast_down(&self, origin: Vec3, max_distance: f32) -> Option<GroundHit> {
+        let height = self.height_at(origin.x, origin.z)?;
+        let distance = origin.y - height;
+
+        (0.0..=max_distance).contains(&distance).then(|| GroundHit {
+            point: Vec3::new(origin.x, height, origin.z),
+            normal: self.normal_at(origin.x, origin.z).unwrap_or(Vec3::Y),
+            distance,
+        })
+    }
+}
+
+/// Returns the x and z position of a grid point
+fn grid_position(settings: &TerrainSettings, column: u32, row: u32) -> Vec2 {
+    let cell_size = settings.size / settings.resolution as f32;
+    Vec2::new(column as f32, row as f32) * cell_size - settings.size / 2.0
+}
+
+/// Layers of value noise from wide hills to small bumps, between -1 and 1
+fn fractal_noise(settings: &TerrainSettings, position: Vec2) -> f32 {
+    let mut total = 0.0;
+    let mut total_weight = 0.0;
+    let mut weight = 1.0;
+    let mut frequency = 1.0 / settings.feature_size;
+
+    for octave in 0..settings.octaves {
+        let seed = settings.seed.wrapping_add(octave);
+        total += value_noise(seed, position * frequency) * weight;
+        total_weight += weight;
+        weight /= 2.0;
+        frequency *= 2.0;
+    }
+
+    if total_weight > 0.0 {
+        total / total_weight
+    } else {
+        0.0
+    }
+}
+
+/// Smoothly interpolates between random values at whole coordinates, between -1 and 1
+fn value_noise(seed: u32, position: Vec2) -> f32 {
+    let cell = position.floor();
+    let fraction = position - cell;
+    let (x, z) = (cell.x as i32, cell.y as i32);
+
+    let smooth = fraction * fraction * (3.0 - 2.0 * fraction);
+    let top = lerp(random(seed, x, z), random(seed, x + 1, z), smooth.x);
+    let bottom = lerp(random(seed, x, z + 1), random(seed, x + 1, z + 1), smooth.x);
+    lerp(top, bottom, smooth.y)
+}
+
+/// Hashes whole coordinates into a value between -1 and 1
+fn random(seed: u32, x: i32, z: i32) -> f32 {
+    let mut hash = seed
+        .wrapping_mul(0x27d4_eb2d)
+        .wrapping_add((x as u32).wrapping_mul(0x8da6_b343))
+        .wrapping_add((z as u32).wrapping_mul(0xd816_3841));
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2c1b_3c6d);
+    hash ^= hash >> 12;
+    hash = hash.wrapping_mul(0x297a_2d39);
+    hash ^= hash >> 15;
+
+    hash as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+fn lerp(from: f32, to: f32, fraction: f32) -> f32 {
+    from + (to - from) * fraction
+}
@@ -1,4 +1,4 @@
-use bevy::prelude::*;
+use glam::{Mat3, Quat, Vec3};
 
 pub fn looking_at(start: Vec3, target: Vec3, up: Vec3) -> Quat {
     looking_towards(target - start, up)
@@ -14,3 +14,83 @@ pub fn looking_towards(direction: Vec3, up: Vec3) -> Quat {
     let up = back.cross(right);
     Quat::from_mat3(&Mat3::from_cols(right, up, back))
 }
+
+/// Removes the part of the vector that points along the normal, the normal should be normalized
+pub fn project_on_plane(vector: Vec3, normal: Vec3) -> Vec3 {
+    vector - normal * normal.dot(vector)
+}
+
+/// Splits a rotation into a swing that moves the twist axis, and a twist around the twist axis.
+/// Returns (swing, twist), with swing * twist giving back the rotation.
+pub fn swing_twist(rotation: Quat, twist_axis: Vec3) -> (Quat, Quat) {
+    let twist_axis = twist_axis.try_normalize().unwrap_or(Vec3::NEG_Z);
+    let rotation_axis = Vec3::new(rotation.x, rotation.y, rotation.z);
+    let projected = twist_axis * twist_axis.dot(rotation_axis);
+
+    // A half turn around an axis perpendicular to the twist axis has no twist at all
+    let twist = Quat::from_xyzw(projected.x, projected.y, projected.z, rotation.w);
+    let twist = if twist.length_squared() > f32::EPSILON {
+        twist.normalize()
+    } else {
+        Quat::IDENTITY
+    };
+    let swing = rotation * twist.conjugate();
+
+    (swing, twist)
+}
+
+/// Signed angle in radians from one vector to another around the normal, after flattening both onto the plane of the normal.
+/// Returns 0 if either of them is parallel to the normal.
+pub fn angle_between_on_plane(from: Vec3, to: Vec3, normal: Vec3) -> f32 {
+    let normal = normal.try_normalize().unwrap_or(Vec3::Y);
+    let from = project_on_plane(from, normal);
+    let to = project_on_plane(to, normal);
+
+    if from.length_squared() <= f32::EPSILON || to.length_squared() <= f32::EPSILON {
+        return 0.0;
+    }
+
+    normal.dot(from.cross(to)).atan2(from.dot(to))
+}
+
+/// Returns the closest direction within `max_angle` radians of the axis, normalized
+pub fn clamp_to_cone(direction: Vec3, axis: Vec3, max_angle: f32) -> Vec3 {
+    let axis = axis.try_normalize().unwrap_or(Vec3::NEG_Z);
+    let direction = direction.try_normalize().unwrap_or(axis);
+    if direction.angle_between(axis) <= max_angle {
+        return direction;
+    }
+
+    let rotation_axis = axis
+        .cross(direction)
+        .try_normalize()
+        .unwrap_or_else(|| axis.any_orthonormal_vector());
+    Quat::from_axis_angle(rotation_axis, max_angle) * axis
+}
+
+/// Limits the swing of a rotation so it turns the axis by at most `max_angle` radians, the twist around the axis is kept
+pub fn clamp_rotation_to_cone(rotation: Quat, axis: Vec3, max_angle: f32) -> Quat {
+    let axis = axis.try_normalize().unwrap_or(Vec3::NEG_Z);
+    let (swing, twist) = swing_twist(rotation, axis);
+
+    let swung_axis = swing * axis;
+    if swung_axis.angle_between(axis) <= max_angle {
+        return rotation;
+    }
+
+    let clamped_axis = clamp_to_cone(swung_axis, axis, max_angle);
+    Quat::from_rotation_arc(axis, clamped_axis) * twist
+}
+
+/// Limits the twist of a rotation around the axis to between `min_angle` and `max_angle` radians, the swing is kept
+pub fn clamp_twist(rotation: Quat, twist_axis: Vec3, min_angle: f32, max_angle: f32) -> Quat {
+    let twist_axis = twist_axis.try_normalize().unwrap_or(Vec3::NEG_Z);
+    let (swing, twist) = swing_twist(rotation, twist_axis);
+
+    // A quaternion and its negation are the same rotation, using the one with a positive w keeps the angle within -PI and PI
+    let sign = if twist.w < 0.0 { -1.0 } else { 1.0 };
+    let along_axis = Vec3::new(twist.x, twist.y, twist.z).dot(twist_axis);
+    let angle = 2.0 * (sign * along_axis).atan2(sign * twist.w);
+
+    swing * Quat::from_axis_angle(twist_axis, angle.clamp(min_angle, max_angle))
+}
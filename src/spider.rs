@@ -1,16 +1,54 @@
-use bevy::{math::vec3, prelude::*};
+use std::f32::consts::PI;
 
-use crate::{
-    ik::{leg::AnimatedLeg, IkChain},
+use bevy::{
+    math::{vec3, Affine3A},
+    prelude::*,
+};
+use walking_ik_spider::{
+    ik::{
+        solver::{ccd::CcdSolver, fabrik::FabrikSolver, jacobian::JacobianSolver, ChainSolver},
+        ChainSpace, IkChain, JointConstraint, PoleTarget,
+    },
+    plugin::{
+        chain_to_world,
+        ground::Ground,
+        leg::{trajectory::StepTrajectory, AnimatedLeg},
+        posture::BodyPosture,
+    },
     rotations,
 };
 
 const SPAWN_POSITION: Vec3 = Vec3::new(-2.0, 1.0, 2.0);
 const MOVE_SPEED: f32 = 6.0;
+const TURN_SPEED: f32 = 1.5;
+
+/// Height of the body above the ground its feet stand on
+const BODY_CLEARANCE: f32 = 1.0;
 
 const LEG_TARGET_OFFSET: Vec3 = Vec3::new(4.0, -0.5, 0.0);
 const LEG_ERROR_THRESHOLD: f32 = 12.0;
 
+/// How fast the legs grow or shrink, and the limits of their size compared to the size they were spawned with
+const LEG_GROW_SPEED: f32 = 0.5;
+const MIN_LEG_SCALE: f32 = 0.5;
+const MAX_LEG_SCALE: f32 = 2.0;
+
+/// Directions the knees bend towards, front legs lean their knees forward and back legs backward
+const KNEE_POLE_FRONT: Vec3 = Vec3::new(0.0, 1.0, -0.4);
+const KNEE_POLE_MIDDLE: Vec3 = Vec3::Y;
+const KNEE_POLE_BACK: Vec3 = Vec3::new(0.0, 1.0, 0.4);
+
+/// Direction the foot points in when planted, so it stands on the ground instead of stabbing into it.
+/// Feet point into the ground they step onto, this is for steps that don't land on any ground.
+const FOOT_DIRECTION: Vec3 = Vec3::NEG_Y;
+
+/// Keeps the knee on the pole side of the line from the hip to the foot, and the upper leg in the plane of the pole
+const HIP_CONSTRAINT: JointConstraint = JointConstraint::Hinge {
+    axis: Vec3::X,
+    min_angle: 0.01,
+    max_angle: PI,
+};
+
 const BODY_COLOR: Color = Color::BLACK;
 const LEGS_COLOR: Color = Color::DARK_GRAY;
 
@@ -22,8 +60,12 @@ impl Plugin for SpiderPlugin {
             Update,
             (
                 move_from_input,
+                select_solver_from_input,
+                select_step_trajectory_from_input,
+                scale_legs_from_input,
                 update_leg_error,
                 retarget_if_threshold_reached,
+                step_legs_out_of_reach,
                 position_leg_pieces_on_chain,
             ),
         );
@@ -34,6 +76,8 @@ impl Plugin for SpiderPlugin {
 pub struct Spider {
     combined_leg_position_error: f32,
     movement_group: u8,
+    /// Size of the legs compared to the size they were spawned with
+    leg_scale: f32,
 }
 
 impl Spider {
@@ -54,14 +98,17 @@ struct LegSpawnInfo {
     position_offset: Vec3,
     angle_offset: f32,
     movement_group: u8,
+    /// Direction the knee bends towards, in body space
+    pole_direction: Vec3,
 }
 
 impl LegSpawnInfo {
-    fn new(pos: Vec3, angle: f32, group: u8) -> Self {
+    fn new(pos: Vec3, angle: f32, group: u8, pole: Vec3) -> Self {
         LegSpawnInfo {
             position_offset: pos,
             angle_offset: angle,
             movement_group: group,
+            pole_direction: pole,
         }
     }
 }
@@ -70,12 +117,15 @@ impl LegSpawnInfo {
 struct LegPiece {
     /// what chain segment this leg piece belongs to
     index_in_chain: u8,
+    /// length of the segment when the leg was spawned, the mesh gets stretched when the segment is longer or shorter
+    spawn_length: f32,
 }
 
 impl LegPiece {
-    fn new(position_in_chain: u8) -> Self {
+    fn new(position_in_chain: u8, spawn_length: f32) -> Self {
         Self {
             index_in_chain: position_in_chain,
+            spawn_length,
         }
     }
 }
@@ -98,7 +148,9 @@ fn spawn_spider(
             Spider {
                 combined_leg_position_error: 0.0,
                 movement_group: 2,
+                leg_scale: 1.0,
             },
+            BodyPosture::new(BODY_CLEARANCE),
             PbrBundle {
                 transform: Transform::from_translation(SPAWN_POSITION),
                 mesh,
@@ -114,7 +166,9 @@ fn spawn_spider_legs(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
 ) {
-    let mesh = meshes.add(shape::Box::new(0.2, 0.2, 3.2).into());
+    let upper_leg_mesh = meshes.add(shape::Box::new(0.2, 0.2, 3.2).into());
+    let lower_leg_mesh = meshes.add(shape::Box::new(0.2, 0.2, 2.6).into());
+    let foot_mesh = meshes.add(shape::Box::new(0.15, 0.15, 0.6).into());
 
     let material = materials.add(StandardMaterial {
         base_color: LEGS_COLOR,
@@ -122,37 +176,59 @@ fn spawn_spider_legs(
         ..default()
     });
 
-    let base_points = vec![
+    let base_points = [
         Vec3::new(0.0, 0.0, 0.0),
         Vec3::new(1.0, 3.0, 0.0),
+        Vec3::new(2.0, 0.6, 0.0),
         Vec3::new(2.0, 0.0, 0.0),
     ];
+    let segment_lengths: Vec<f32> = base_points
+        .windows(2)
+        .map(|segment| segment[0].distance(segment[1]))
+        .collect();
 
     let legs_data = [
-        LegSpawnInfo::new(vec3(0.5, 0.0, -0.8), 40.0, 1),
-        LegSpawnInfo::new(vec3(0.5, 0.0, -0.4), 10.0, 2),
-        LegSpawnInfo::new(vec3(0.5, 0.0, 0.4), -10.0, 1),
-        LegSpawnInfo::new(vec3(0.5, 0.0, 0.8), -40.0, 2),
-        LegSpawnInfo::new(vec3(-0.5, 0.0, -0.8), 140.0, 2),
-        LegSpawnInfo::new(vec3(-0.5, 0.0, -0.4), 170.0, 1),
-        LegSpawnInfo::new(vec3(-0.5, 0.0, 0.4), 190.0, 2),
-        LegSpawnInfo::new(vec3(-0.5, 0.0, 0.8), 220.0, 1),
+        LegSpawnInfo::new(vec3(0.5, 0.0, -0.8), 40.0, 1, KNEE_POLE_FRONT),
+        LegSpawnInfo::new(vec3(0.5, 0.0, -0.4), 10.0, 2, KNEE_POLE_MIDDLE),
+        LegSpawnInfo::new(vec3(0.5, 0.0, 0.4), -10.0, 1, KNEE_POLE_MIDDLE),
+        LegSpawnInfo::new(vec3(0.5, 0.0, 0.8), -40.0, 2, KNEE_POLE_BACK),
+        LegSpawnInfo::new(vec3(-0.5, 0.0, -0.8), 140.0, 2, KNEE_POLE_FRONT),
+        LegSpawnInfo::new(vec3(-0.5, 0.0, -0.4), 170.0, 1, KNEE_POLE_MIDDLE),
+        LegSpawnInfo::new(vec3(-0.5, 0.0, 0.4), 190.0, 2, KNEE_POLE_MIDDLE),
+        LegSpawnInfo::new(vec3(-0.5, 0.0, 0.8), 220.0, 1, KNEE_POLE_BACK),
     ];
 
     for data in legs_data.iter() {
         let rotation = Quat::from_axis_angle(Vec3::Y, data.angle_offset.to_radians());
+        // The legs are in the local space of the body, so they follow it around
         let points_of_current_leg = base_points
             .iter()
-            .map(|point| SPAWN_POSITION + data.position_offset + (rotation * *point))
+            .map(|point| data.position_offset + (rotation * *point))
             .collect();
 
+        let chain = match IkChain::new(points_of_current_leg)
+            .and_then(|chain| chain.with_constraints(vec![HIP_CONSTRAINT]))
+        {
+            Ok(chain) => chain
+                .with_space(ChainSpace::Local)
+                .with_pole(PoleTarget::Direction(data.pole_direction))
+                .with_end_orientation(foot_orientation(FOOT_DIRECTION)),
+            Err(error) => {
+                error!("Couldn't spawn spider leg: {}", error);
+                continue;
+            }
+        };
+
         let start = base_points[0];
-        let target = start + (rotation * LEG_TARGET_OFFSET);
+        // Moved onto the ground before the first update
+        let target = SPAWN_POSITION + data.position_offset + rotation * (start + LEG_TARGET_OFFSET);
 
         spider
             .spawn((
-                IkChain::new(points_of_current_leg),
+                chain,
                 AnimatedLeg::new(rotation * LEG_TARGET_OFFSET, target),
+                ChainSolver::default(),
+                StepTrajectory::default(),
                 SpiderLeg {
                     movement_group: data.movement_group,
                 },
@@ -165,11 +241,11 @@ fn spawn_spider_legs(
                     PbrBundle {
                         // Position is wrong but it gets fixed in the first update
                         transform: Transform::from_translation(SPAWN_POSITION),
-                        mesh: mesh.clone(),
+                        mesh: upper_leg_mesh.clone(),
                         material: material.clone(),
                         ..default()
                     },
-                    LegPiece::new(0),
+                    LegPiece::new(0, segment_lengths[0]),
                 ));
 
                 // Lower leg
@@ -177,34 +253,44 @@ fn spawn_spider_legs(
                     PbrBundle {
                         // Position is wrong but it gets fixed in the first update
                         transform: Transform::from_translation(SPAWN_POSITION),
-                        mesh: mesh.clone(),
+                        mesh: lower_leg_mesh.clone(),
+                        material: material.clone(),
+                        ..default()
+                    },
+                    LegPiece::new(1, segment_lengths[1]),
+                ));
+
+                // Foot
+                chain.spawn((
+                    PbrBundle {
+                        // Position is wrong but it gets fixed in the first update
+                        transform: Transform::from_translation(SPAWN_POSITION),
+                        mesh: foot_mesh.clone(),
                         material: material.clone(),
                         ..default()
                     },
-                    LegPiece::new(1),
+                    LegPiece::new(2, segment_lengths[2]),
                 ));
             });
     }
 }
 
 fn move_from_input(
-    mut spider: Query<(&mut Transform, &Children), With<Spider>>,
-    mut spider_legs: Query<&mut IkChain, With<SpiderLeg>>,
+    mut spider: Query<&mut Transform, With<Spider>>,
     input: Res<Input<KeyCode>>,
     time: Res<Time>,
 ) {
-    let (mut transform, children) = spider.single_mut();
+    let mut transform = spider.single_mut();
 
+    let turn_input = get_turn_input(&input);
+    transform.rotate_y(turn_input * time.delta_seconds() * TURN_SPEED);
+
+    // Movement is relative to the direction the spider is facing, leaving out the tilt so it doesn't walk into the ground
+    let (_, heading) = rotations::swing_twist(transform.rotation, Vec3::Y);
     let move_input = get_wasd_input_as_vector(&input);
-    let delta_position = move_input * time.delta_seconds() * MOVE_SPEED;
+    let delta_position = heading * move_input * time.delta_seconds() * MOVE_SPEED;
 
     transform.translation += delta_position;
-
-    for &child_id in children.iter() {
-        if let Ok(mut leg) = spider_legs.get_mut(child_id) {
-            leg.move_start(delta_position);
-        }
-    }
 }
 
 fn get_wasd_input_as_vector(input: &Res<Input<KeyCode>>) -> Vec3 {
@@ -226,25 +312,162 @@ fn get_wasd_input_as_vector(input: &Res<Input<KeyCode>>) -> Vec3 {
     result.normalize_or_zero()
 }
 
+/// returns the yaw direction to turn in, positive is counter-clockwise when seen from above
+fn get_turn_input(input: &Res<Input<KeyCode>>) -> f32 {
+    let mut result = 0.0;
+
+    if input.pressed(KeyCode::Q) {
+        result += 1.0;
+    }
+    if input.pressed(KeyCode::E) {
+        result -= 1.0;
+    }
+
+    result
+}
+
+/// returns 1 to grow the legs, -1 to shrink them
+fn get_grow_input(input: &Res<Input<KeyCode>>) -> f32 {
+    let mut result = 0.0;
+
+    if input.pressed(KeyCode::Up) {
+        result += 1.0;
+    }
+    if input.pressed(KeyCode::Down) {
+        result -= 1.0;
+    }
+
+    result
+}
+
+/// switches the IK solver of all legs, 1 for FABRIK, 2 for CCD and 3 for the Jacobian solver
+fn select_solver_from_input(
+    mut spider_legs: Query<&mut ChainSolver, With<SpiderLeg>>,
+    input: Res<Input<KeyCode>>,
+) {
+    let selected_solver = if input.just_pressed(KeyCode::Key1) {
+        ChainSolver::Fabrik(FabrikSolver)
+    } else if input.just_pressed(KeyCode::Key2) {
+        ChainSolver::Ccd(CcdSolver)
+    } else if input.just_pressed(KeyCode::Key3) {
+        ChainSolver::Jacobian(JacobianSolver::default())
+    } else {
+        return;
+    };
+
+    for mut solver in spider_legs.iter_mut() {
+        *solver = selected_solver;
+    }
+}
+
+/// grows the legs with the up arrow and shrinks them with the down arrow
+fn scale_legs_from_input(
+    mut spider: Query<(&mut Spider, &mut BodyPosture, &Children)>,
+    mut spider_legs: Query<(&mut IkChain, &mut AnimatedLeg, &Children), With<SpiderLeg>>,
+    leg_pieces: Query<&LegPiece>,
+    input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    let grow_input = get_grow_input(&input);
+    if grow_input == 0.0 {
+        return;
+    }
+
+    let (mut spider, mut posture, children) = spider.single_mut();
+    let previous_scale = spider.leg_scale;
+    spider.leg_scale = (previous_scale + grow_input * LEG_GROW_SPEED * time.delta_seconds())
+        .clamp(MIN_LEG_SCALE, MAX_LEG_SCALE);
+
+    // Longer legs also stand taller
+    posture.clearance *= spider.leg_scale / previous_scale;
+
+    for &child in children.iter() {
+        let Ok((mut chain, mut leg, pieces)) = spider_legs.get_mut(child) else {
+            continue;
+        };
+
+        // Longer legs also step further out
+        leg.reposition_target_offset *= spider.leg_scale / previous_scale;
+
+        for piece in leg_pieces.iter_many(pieces) {
+            let length = piece.spawn_length * spider.leg_scale;
+            if let Err(error) = chain.set_segment_length(piece.index_in_chain as usize, length) {
+                warn!("Couldn't scale spider leg: {}", error);
+            }
+        }
+    }
+}
+
+/// switches the way all legs step, Z for the default steps, X for skittish, C for heavy and V for mechanical steps
+fn select_step_trajectory_from_input(
+    mut spider_legs: Query<&mut StepTrajectory, With<SpiderLeg>>,
+    input: Res<Input<KeyCode>>,
+) {
+    let selected_trajectory = if input.just_pressed(KeyCode::Z) {
+        StepTrajectory::default()
+    } else if input.just_pressed(KeyCode::X) {
+        StepTrajectory::skittish()
+    } else if input.just_pressed(KeyCode::C) {
+        StepTrajectory::heavy()
+    } else if input.just_pressed(KeyCode::V) {
+        StepTrajectory::mechanical()
+    } else {
+        return;
+    };
+
+    for mut trajectory in spider_legs.iter_mut() {
+        *trajectory = selected_trajectory.clone();
+    }
+}
+
 fn update_leg_error(
     mut spider: Query<(&mut Spider, &Children)>,
-    spider_legs: Query<(&IkChain, &AnimatedLeg), With<SpiderLeg>>,
+    spider_legs: Query<(&IkChain, &AnimatedLeg, &GlobalTransform), With<SpiderLeg>>,
 ) {
     let (mut spider, children) = spider.single_mut();
 
     let combined_error = children
         .iter()
         .filter_map(|&child| spider_legs.get(child).ok())
-        .fold(0.0, |combined, (chain, leg)| {
-            combined + (chain.start + leg.reposition_target_offset).distance(leg.current_target)
+        .fold(0.0, |combined, (chain, leg, global_transform)| {
+            let ideal_target =
+                leg.ideal_target(chain, chain_to_world(chain, Some(global_transform)));
+
+            // Only the horizontal error counts, feet planted on the ground are rarely at the height of the ideal target
+            let error = (ideal_target - leg.current_target) * Vec3::new(1.0, 0.0, 1.0);
+            combined + error.length()
         });
 
     spider.combined_leg_position_error = combined_error;
 }
 
+/// Orientation of a foot pointing in the given direction, in the space of the leg chain
+fn foot_orientation(direction: Vec3) -> Quat {
+    rotations::looking_towards(direction, Vec3::NEG_Z)
+}
+
+/// Starts the next step of a leg onto the ground, the foot turns to stand flat on the ground it lands on
+fn step_onto_ground(
+    chain: &mut IkChain,
+    leg: &mut AnimatedLeg,
+    global_transform: &GlobalTransform,
+    ground: &Ground,
+) {
+    let to_world = chain_to_world(chain, Some(global_transform));
+    let step = leg.grounded_step(chain, to_world, ground);
+
+    let foot_direction = step.ground_normal.map_or(FOOT_DIRECTION, |normal| {
+        -to_world.inverse().transform_vector3(normal).normalize()
+    });
+    chain.end_orientation = Some(foot_orientation(foot_direction));
+
+    leg.start_step(step);
+}
+
 fn retarget_if_threshold_reached(
     mut spider: Query<(&mut Spider, &Children)>,
-    mut spider_legs: Query<(&IkChain, &mut AnimatedLeg, &SpiderLeg)>,
+    mut spider_legs: Query<(&mut IkChain, &mut AnimatedLeg, &SpiderLeg, &GlobalTransform)>,
+    ground: Res<Ground>,
 ) {
     let (mut spider, children) = spider.single_mut();
 
@@ -254,16 +477,29 @@ fn retarget_if_threshold_reached(
         // get all legs of current movement group
         // set their current target to new position
         for &child_id in children.iter() {
-            if let Ok((chain, mut leg, spider_leg)) = spider_legs.get_mut(child_id) {
+            if let Ok((mut chain, mut leg, spider_leg, global_transform)) =
+                spider_legs.get_mut(child_id)
+            {
                 if spider_leg.movement_group == spider.movement_group {
-                    let target = chain.start + leg.reposition_target_offset;
-                    leg.set_new_target(target);
+                    step_onto_ground(&mut chain, &mut leg, global_transform, &ground);
                 }
             }
         }
     }
 }
 
+/// gives legs that can't reach their target anymore a new target, without waiting for the rest of their movement group
+fn step_legs_out_of_reach(
+    mut spider_legs: Query<(&mut IkChain, &mut AnimatedLeg, &GlobalTransform), With<SpiderLeg>>,
+    ground: Res<Ground>,
+) {
+    for (mut chain, mut leg, global_transform) in spider_legs.iter_mut() {
+        if !chain.last_report().reachable && !leg.is_stepping() {
+            step_onto_ground(&mut chain, &mut leg, global_transform, &ground);
+        }
+    }
+}
+
 /// updates the position of the leg piece objects on the chain they belong to
 fn position_leg_pieces_on_chain(
     spider_legs: Query<(&IkChain, &GlobalTransform, &Children), With<SpiderLeg>>,
@@ -272,16 +508,26 @@ fn position_leg_pieces_on_chain(
     for (chain, global_transform, children) in spider_legs.iter() {
         for &child_id in children.iter() {
             if let Ok((leg, mut transform)) = leg_pieces.get_mut(child_id) {
-                let segment = chain.get_segment(leg.index_in_chain as usize);
+                let Ok(segment) = chain.get_segment(leg.index_in_chain as usize) else {
+                    continue;
+                };
 
+                // The segment orientation keeps its twist, so pieces don't flip around when they point straight down
                 let segment_direction = (segment.end - segment.start).normalize_or_zero();
-                let segment_orientation = rotations::looking_towards(segment_direction, Vec3::Y);
                 let segment_middle = segment.start + segment_direction * segment.length / 2.0;
 
-                let local_position = segment_middle - global_transform.translation();
-
-                transform.translation = local_position;
-                transform.rotation = segment_orientation;
+                // Leg pieces are children of the leg, so go from the space of the chain to the local space of the leg
+                let chain_to_leg = global_transform.affine().inverse()
+                    * chain_to_world(chain, Some(global_transform));
+                // Stretch the piece along its length when the segment isn't as long as it was at spawn
+                let scale = Vec3::new(1.0, 1.0, segment.length / leg.spawn_length);
+                let segment_transform = Affine3A::from_scale_rotation_translation(
+                    scale,
+                    segment.orientation,
+                    segment_middle,
+                );
+
+                *transform = Transform::from_matrix((chain_to_leg * segment_transform).into());
             }
         }
     }
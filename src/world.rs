@@ -1,6 +1,10 @@
 use std::f32::consts::PI;
 
 use bevy::prelude::*;
+use walking_ik_spider::plugin::{
+    ground::{Ground, GroundCollider},
+    terrain::{Terrain, TerrainSettings},
+};
 
 pub struct WorldPlugin;
 
@@ -14,29 +18,37 @@ fn spawn_objects(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut ground: ResMut<Ground>,
 ) {
-    // Ground
-    commands.spawn((PbrBundle {
-        mesh: meshes.add(shape::Box::new(200.0, 0.2, 200.0).into()),
+    // Terrain
+    let terrain = Terrain::new(TerrainSettings::default());
+    commands.spawn(PbrBundle {
+        mesh: meshes.add(terrain.mesh()),
         material: materials.add(StandardMaterial {
             base_color: Color::GRAY,
+            perceptual_roughness: 1.0,
             ..default()
         }),
-        transform: Transform::from_xyz(0.0, 0.0, 0.0),
         ..default()
-    },));
+    });
+    // Sunk into the terrain a bit so it doesn't float where the ground slopes away
+    let cube_height = terrain.height_at(0.0, 0.0).unwrap_or(0.0) + 0.5;
+    ground.add_query(terrain);
 
     // Cube
-    commands.spawn((PbrBundle {
-        mesh: meshes.add(shape::Cube::new(2.0).into()),
-        material: materials.add(StandardMaterial {
-            base_color: Color::ORANGE,
-            perceptual_roughness: 1.0,
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(shape::Cube::new(2.0).into()),
+            material: materials.add(StandardMaterial {
+                base_color: Color::ORANGE,
+                perceptual_roughness: 1.0,
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, cube_height, 0.0),
             ..default()
-        }),
-        transform: Transform::from_xyz(0.0, 1.0, 0.0),
-        ..default()
-    },));
+        },
+        GroundCollider::new(Vec3::splat(2.0)),
+    ));
 
     // Light
     commands.spawn(DirectionalLightBundle {
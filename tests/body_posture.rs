@@ -0,0 +1,252 @@
+#![cfg(feature = "bevy")]
+
+mod common;
+
+use bevy::prelude::*;
+use common::TestWorld;
+use walking_ik_spider::{
+    plugin::{
+        ground::{update_ground_colliders, Ground},
+        leg::{place_new_legs_on_ground, AnimatedLeg},
+        posture::{adapt_body_to_planted_feet, BodyPosture, FootPlane},
+        terrain::{Terrain, TerrainSettings},
+    },
+    rotations,
+};
+
+const EPSILON: f32 = 1e-4;
+
+/// Feet around the origin on a plane rising by `x_slope` along x and `z_slope` along z
+fn feet_on_slope(x_slope: f32, z_slope: f32) -> Vec<Vec3> {
+    [
+        (2.0, 2.0),
+        (-2.0, 2.0),
+        (2.0, -2.0),
+        (-2.0, -2.0),
+        (3.0, 0.0),
+    ]
+    .into_iter()
+    .map(|(x, z)| Vec3::new(x, 1.0 + x * x_slope + z * z_slope, z))
+    .collect()
+}
+
+#[test]
+fn a_plane_fits_feet_on_flat_ground() {
+    let plane = FootPlane::fit(&feet_on_slope(0.0, 0.0)).unwrap();
+
+    assert!(plane.normal.distance(Vec3::Y) <= EPSILON);
+    assert!((plane.height_at(10.0, -4.0) - 1.0).abs() <= EPSILON);
+}
+
+#[test]
+fn a_plane_fits_feet_on_a_slope() {
+    let plane = FootPlane::fit(&feet_on_slope(0.5, -0.25)).unwrap();
+
+    let expected_normal = Vec3::new(-0.5, 1.0, 0.25).normalize();
+    assert!(plane.normal.distance(expected_normal) <= EPSILON);
+    assert!((plane.height_at(4.0, 2.0) - 2.5).abs() <= EPSILON);
+}
+
+#[test]
+fn a_plane_fits_uneven_feet_on_average() {
+    let mut feet = feet_on_slope(0.0, 0.0);
+    feet[0].y += 0.5;
+    feet[3].y += 0.5;
+    feet[1].y -= 0.5;
+    feet[2].y -= 0.5;
+
+    let plane = FootPlane::fit(&feet).unwrap();
+
+    assert!(plane.normal.distance(Vec3::Y) <= EPSILON);
+    assert!((plane.center.y - 1.0).abs() <= EPSILON);
+}
+
+#[test]
+fn too_few_feet_or_feet_in_a_line_fit_no_plane() {
+    assert_eq!(FootPlane::fit(&[Vec3::ZERO, Vec3::X]), None);
+
+    let in_a_line = [
+        Vec3::ZERO,
+        Vec3::new(1.0, 0.5, 1.0),
+        Vec3::new(3.0, 1.0, 3.0),
+    ];
+    assert_eq!(FootPlane::fit(&in_a_line), None);
+}
+
+#[test]
+fn the_target_posture_keeps_the_heading() {
+    let posture = BodyPosture::new(1.5);
+    let plane = FootPlane::fit(&feet_on_slope(0.0, 0.3)).unwrap();
+    let heading = Quat::from_rotation_y(0.8);
+
+    let (height, rotation) = posture.target(&plane, Vec3::new(0.0, 5.0, 1.0), heading);
+
+    assert!((height - 2.8).abs() <= EPSILON);
+    assert!((rotation * Vec3::Y).distance(plane.normal) <= EPSILON);
+    // The tilt is added on top of the heading, turning the body around the vertical axis is left to whatever steers it
+    let (_, twist) = rotations::swing_twist(rotation, Vec3::Y);
+    assert!(twist.angle_between(heading) <= EPSILON * 10.0);
+}
+
+#[test]
+fn the_target_posture_limits_the_tilt() {
+    let posture = BodyPosture {
+        tilt_factor: 0.5,
+        max_tilt: 0.2,
+        ..BodyPosture::new(1.0)
+    };
+    let gentle = FootPlane::fit(&feet_on_slope(0.2, 0.0)).unwrap();
+    let steep = FootPlane::fit(&feet_on_slope(3.0, 0.0)).unwrap();
+
+    let (_, rotation) = posture.target(&gentle, Vec3::ZERO, Quat::IDENTITY);
+    let half_tilt = Vec3::Y.lerp(gentle.normal, 0.5).normalize();
+    assert!((rotation * Vec3::Y).distance(half_tilt) <= EPSILON);
+
+    let (_, rotation) = posture.target(&steep, Vec3::ZERO, Quat::IDENTITY);
+    assert!(((rotation * Vec3::Y).angle_between(Vec3::Y) - 0.2).abs() <= EPSILON);
+}
+
+struct PostureWorld {
+    test: TestWorld,
+    body: Entity,
+    legs: Vec<Entity>,
+}
+
+impl PostureWorld {
+    fn new(feet: Vec<Vec3>) -> Self {
+        let mut test = TestWorld::new().with_systems(adapt_body_to_planted_feet);
+
+        let legs: Vec<Entity> = feet
+            .into_iter()
+            .map(|foot| test.world.spawn(AnimatedLeg::new(Vec3::ZERO, foot)).id())
+            .collect();
+        let body = test
+            .world
+            .spawn((TransformBundle::default(), BodyPosture::new(1.0)))
+            .push_children(&legs)
+            .id();
+
+        PostureWorld { test, body, legs }
+    }
+
+    /// Runs the posture system a number of times, a tenth of a second apart
+    fn update(&mut self, times: u32) {
+        for _ in 0..times {
+            self.test.update(0.1);
+        }
+    }
+
+    fn body(&self) -> Transform {
+        *self.test.get::<Transform>(self.body)
+    }
+}
+
+#[test]
+fn the_body_settles_above_the_planted_feet() {
+    let mut world = PostureWorld::new(feet_on_slope(0.0, 0.5));
+
+    world.update(1);
+    let first = world.body();
+    assert!(first.translation.y > 0.0 && first.translation.y < 2.0);
+
+    world.update(30);
+    let settled = world.body();
+    let expected_up = Vec3::new(0.0, 1.0, -0.5).normalize();
+    assert!((settled.translation.y - 2.0).abs() <= 1e-3);
+    assert!((settled.rotation * Vec3::Y).distance(expected_up) <= 1e-3);
+}
+
+#[test]
+fn stepping_feet_are_left_out() {
+    let mut world = PostureWorld::new(feet_on_slope(0.0, 0.0));
+    let stepping_leg = world.legs[0];
+    world
+        .test
+        .get_mut::<AnimatedLeg>(stepping_leg)
+        .set_new_target(Vec3::new(2.0, 10.0, 2.0));
+
+    world.update(30);
+
+    let body = world.body();
+    assert!((body.translation.y - 2.0).abs() <= 1e-3);
+    assert!((body.rotation * Vec3::Y).distance(Vec3::Y) <= 1e-3);
+}
+
+#[test]
+fn the_body_keeps_its_posture_without_enough_planted_feet() {
+    let mut world = PostureWorld::new(feet_on_slope(0.0, 0.0));
+    for &leg in &world.legs[..3] {
+        world
+            .test
+            .get_mut::<AnimatedLeg>(leg)
+            .set_new_target(Vec3::new(0.0, 10.0, 0.0));
+    }
+
+    world.update(10);
+
+    assert_eq!(world.body(), Transform::default());
+}
+
+#[test]
+fn a_body_spawned_on_uneven_terrain_fits_the_terrain() {
+    let terrain = Terrain::new(TerrainSettings {
+        seed: 4,
+        size: 40.0,
+        resolution: 40,
+        amplitude: 3.0,
+        feature_size: 12.0,
+        ..default()
+    });
+    let mut ground = Ground::default();
+    ground.add_query(terrain.clone());
+
+    let mut test = TestWorld::new().with_systems(
+        (
+            update_ground_colliders,
+            place_new_legs_on_ground,
+            adapt_body_to_planted_feet,
+        )
+            .chain(),
+    );
+    test.world.insert_resource(ground);
+
+    // Spawned like the spider, with the feet around the body at a fixed height that is in the air over most of the terrain
+    let body_position = Vec3::new(-2.0, 1.0, 2.0);
+    let spawn_feet: Vec<Vec3> = (0..8)
+        .map(|index| {
+            let angle = index as f32 * std::f32::consts::TAU / 8.0;
+            body_position + Quat::from_rotation_y(angle) * Vec3::new(4.0, -0.5, 0.0)
+        })
+        .collect();
+    let legs: Vec<Entity> = spawn_feet
+        .iter()
+        .map(|&foot| test.world.spawn(AnimatedLeg::new(Vec3::ZERO, foot)).id())
+        .collect();
+    test.world
+        .spawn((
+            TransformBundle::from_transform(Transform::from_translation(body_position)),
+            BodyPosture::new(1.0),
+        ))
+        .push_children(&legs);
+
+    for _ in 0..30 {
+        test.update(0.1);
+    }
+
+    let grounded_feet: Vec<Vec3> = spawn_feet
+        .iter()
+        .map(|foot| Vec3::new(foot.x, terrain.height_at(foot.x, foot.z).unwrap(), foot.z))
+        .collect();
+    let terrain_plane = FootPlane::fit(&grounded_feet).unwrap();
+    let expected_height = terrain_plane.height_at(body_position.x, body_position.z) + 1.0;
+    // Feet left floating at their spawn height would hold the body at 1.5 and upright
+    assert!((expected_height - 1.5).abs() > 0.2);
+    assert!(terrain_plane.normal.angle_between(Vec3::Y) > 0.02);
+
+    let body = *test
+        .world
+        .query_filtered::<&Transform, With<BodyPosture>>()
+        .single(&test.world);
+    assert!((body.translation.y - expected_height).abs() <= 1e-3);
+    assert!((body.rotation * Vec3::Y).distance(terrain_plane.normal) <= 1e-3);
+}
@@ -0,0 +1,72 @@
+//! Helpers shared by the integration tests that run Bevy systems
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+use bevy::{ecs::event::Events, prelude::*};
+use walking_ik_spider::ik::IkChain;
+
+/// A world with a manually ticked clock, so systems see exactly the time steps a test asks for
+pub struct TestWorld {
+    pub world: World,
+    pub schedule: Schedule,
+    now: Instant,
+}
+
+impl TestWorld {
+    pub fn new() -> Self {
+        let mut world = World::new();
+        let now = Instant::now();
+
+        let mut time = Time::default();
+        time.update_with_instant(now);
+        world.insert_resource(time);
+
+        TestWorld {
+            world,
+            schedule: Schedule::default(),
+            now,
+        }
+    }
+
+    pub fn with_systems<M>(mut self, systems: impl IntoSystemConfigs<M>) -> Self {
+        self.schedule.add_systems(systems);
+        self
+    }
+
+    pub fn with_event<E: Event>(mut self) -> Self {
+        self.world.init_resource::<Events<E>>();
+        self
+    }
+
+    /// Runs the systems after the given amount of seconds has passed
+    pub fn update(&mut self, seconds: f32) {
+        self.now += Duration::from_secs_f32(seconds);
+        let now = self.now;
+        self.world.resource_mut::<Time>().update_with_instant(now);
+        self.schedule.run(&mut self.world);
+    }
+
+    pub fn get<T: Component>(&self, entity: Entity) -> &T {
+        self.world.get::<T>(entity).unwrap()
+    }
+
+    pub fn get_mut<T: Component>(&mut self, entity: Entity) -> Mut<'_, T> {
+        self.world.get_mut::<T>(entity).unwrap()
+    }
+
+    /// Returns the events sent since the last drain
+    pub fn drain_events<E: Event>(&mut self) -> Vec<E> {
+        self.world.resource_mut::<Events<E>>().drain().collect()
+    }
+}
+
+/// A two segment leg chain that starts at the origin and ends at `(2, 0, 0)`
+pub fn leg_chain() -> IkChain {
+    let points = vec![
+        Vec3::ZERO,
+        Vec3::new(1.0, 3.0, 0.0),
+        Vec3::new(2.0, 0.0, 0.0),
+    ];
+    IkChain::new(points).unwrap()
+}
@@ -0,0 +1,179 @@
+#![cfg(feature = "bevy")]
+
+mod common;
+
+use bevy::prelude::*;
+use common::{leg_chain, TestWorld};
+use walking_ik_spider::{
+    ik::{ChainSpace, IkChain},
+    plugin::{
+        chain_to_world,
+        ground::{update_ground_colliders, Ground, GroundCollider},
+        leg::{animate_leg_towards_target, place_new_legs_on_ground, AnimatedLeg},
+        terrain::{Terrain, TerrainSettings},
+    },
+};
+
+const EPSILON: f32 = 1e-3;
+const BODY_VELOCITY: Vec3 = Vec3::new(0.0, 0.0, -6.0);
+const DELTA_SECONDS: f32 = 0.01;
+
+/// Spawns a leg with its foot planted behind the body, and moves it for a few updates so its body velocity is known
+fn moving_leg() -> (TestWorld, Entity) {
+    let mut test = TestWorld::new().with_systems(animate_leg_towards_target);
+
+    let leg = test
+        .world
+        .spawn((
+            leg_chain().with_space(ChainSpace::Local),
+            AnimatedLeg::new(Vec3::new(3.0, -1.0, 0.0), Vec3::new(3.0, -1.0, 3.0)),
+            GlobalTransform::default(),
+        ))
+        .id();
+
+    for _ in 0..3 {
+        move_body(&mut test, leg);
+    }
+
+    (test, leg)
+}
+
+/// Moves the leg entity along with the body for one update
+fn move_body(test: &mut TestWorld, leg: Entity) {
+    let position = test.get::<GlobalTransform>(leg).translation();
+    *test.get_mut::<GlobalTransform>(leg) =
+        GlobalTransform::from_translation(position + BODY_VELOCITY * DELTA_SECONDS);
+
+    test.update(DELTA_SECONDS);
+}
+
+fn ideal_target(test: &TestWorld, leg: Entity) -> Vec3 {
+    let chain = test.get::<IkChain>(leg);
+    let global_transform = test.get::<GlobalTransform>(leg);
+
+    test.get::<AnimatedLeg>(leg)
+        .ideal_target(chain, chain_to_world(chain, Some(global_transform)))
+}
+
+#[test]
+fn body_velocity_is_measured_from_the_leg_movement() {
+    let (test, leg) = moving_leg();
+    let animated_leg = test.get::<AnimatedLeg>(leg);
+
+    assert!(animated_leg.body_velocity().distance(BODY_VELOCITY) <= EPSILON);
+}
+
+#[test]
+fn a_predicted_step_lands_on_the_ideal_target_of_the_moved_body() {
+    let (mut test, leg) = moving_leg();
+
+    let chain = test.get::<IkChain>(leg);
+    let to_world = chain_to_world(chain, Some(test.get::<GlobalTransform>(leg)));
+    let step = test.get::<AnimatedLeg>(leg).predicted_step(chain, to_world);
+    test.get_mut::<AnimatedLeg>(leg).start_step(step);
+
+    let mut elapsed = 0.0;
+    while test.get::<AnimatedLeg>(leg).is_stepping() {
+        move_body(&mut test, leg);
+        elapsed += DELTA_SECONDS;
+        assert!(elapsed < 1.0, "the step never landed");
+    }
+
+    // The step lasts as long as the prediction looked ahead, give or take the update it lands in
+    assert!((elapsed - step.duration).abs() <= DELTA_SECONDS + EPSILON);
+    let landed = test.get::<AnimatedLeg>(leg).current_target;
+    let distance = landed.distance(ideal_target(&test, leg));
+    assert!(
+        distance <= BODY_VELOCITY.length() * DELTA_SECONDS + EPSILON,
+        "landed {} from the ideal target",
+        distance
+    );
+}
+
+#[test]
+fn predicted_step_of_a_resting_body_goes_to_the_ideal_target() {
+    let chain = leg_chain();
+    let animated_leg = AnimatedLeg::new(Vec3::new(3.0, -1.0, 0.0), Vec3::ZERO);
+
+    let to_world = chain_to_world(&chain, None);
+    assert_eq!(
+        animated_leg.predicted_step(&chain, to_world).target,
+        animated_leg.ideal_target(&chain, to_world)
+    );
+}
+
+#[test]
+fn new_legs_start_with_their_feet_on_the_ground() {
+    let terrain = Terrain::new(TerrainSettings {
+        size: 20.0,
+        resolution: 10,
+        ..default()
+    });
+    let mut ground = Ground::default();
+    ground.add_query(terrain.clone());
+
+    let mut test =
+        TestWorld::new().with_systems((update_ground_colliders, place_new_legs_on_ground).chain());
+    test.world.insert_resource(ground);
+    test.world.spawn((
+        GroundCollider::new(Vec3::splat(2.0)),
+        GlobalTransform::from_xyz(5.0, 4.0, 5.0),
+    ));
+
+    let spawn_targets = [Vec3::new(-3.0, 0.5, 2.0), Vec3::new(5.0, 4.5, 5.0)];
+    let legs: Vec<Entity> = spawn_targets
+        .iter()
+        .map(|&target| test.world.spawn(AnimatedLeg::new(Vec3::ZERO, target)).id())
+        .collect();
+
+    test.update(0.1);
+
+    let on_terrain = test.get::<AnimatedLeg>(legs[0]);
+    let terrain_height = terrain.height_at(-3.0, 2.0).unwrap();
+    assert!((on_terrain.current_target.y - terrain_height).abs() <= EPSILON);
+    assert_eq!(on_terrain.previous_target, on_terrain.current_target);
+    assert!(!on_terrain.is_stepping());
+
+    let on_collider = test.get::<AnimatedLeg>(legs[1]);
+    assert!(
+        on_collider
+            .current_target
+            .distance(Vec3::new(5.0, 5.0, 5.0))
+            <= EPSILON
+    );
+}
+
+#[test]
+fn grounded_steps_carry_the_normal_of_the_ground_they_land_on() {
+    let slope = Transform::from_rotation(Quat::from_rotation_z(0.3));
+    let mut test = TestWorld::new().with_systems(update_ground_colliders);
+    test.world.init_resource::<Ground>();
+    test.world.spawn((
+        GroundCollider::new(Vec3::new(20.0, 1.0, 20.0)),
+        GlobalTransform::from(slope),
+    ));
+    test.update(0.1);
+    let ground = test.world.resource::<Ground>();
+
+    let chain = leg_chain();
+    let to_world = chain_to_world(&chain, None);
+    let on_slope = AnimatedLeg::new(Vec3::new(3.0, 0.0, 0.0), Vec3::ZERO);
+    let step = on_slope.grounded_step(&chain, to_world, ground);
+
+    assert!(
+        step.ground_normal
+            .unwrap()
+            .distance(slope.rotation * Vec3::Y)
+            <= EPSILON
+    );
+    assert_eq!(
+        step.duration,
+        on_slope.predicted_step(&chain, to_world).duration
+    );
+
+    let off_the_edge = AnimatedLeg::new(Vec3::new(30.0, 0.0, 0.0), Vec3::ZERO);
+    let step = off_the_edge.grounded_step(&chain, to_world, ground);
+
+    assert_eq!(step.ground_normal, None);
+    assert_eq!(step.target, off_the_edge.ideal_target(&chain, to_world));
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "bevy")]
+
+use bevy::prelude::*;
+use walking_ik_spider::plugin::ground::{
+    update_ground_colliders, Ground, GroundCollider, GroundHit, GroundQuery,
+};
+
+const EPSILON: f32 = 1e-4;
+
+/// Runs the collider update once over the given colliders
+fn ground_with_colliders(colliders: Vec<(GroundCollider, Transform)>) -> Ground {
+    let mut world = World::new();
+    world.init_resource::<Ground>();
+
+    for (collider, transform) in colliders {
+        world.spawn((collider, GlobalTransform::from(transform)));
+    }
+
+    let mut schedule = Schedule::default();
+    schedule.add_systems(update_ground_colliders);
+    schedule.run(&mut world);
+
+    world.remove_resource::<Ground>().unwrap()
+}
+
+/// A flat floor and a cube on top of it
+fn floor_and_cube() -> Ground {
+    ground_with_colliders(vec![
+        (
+            GroundCollider::new(Vec3::new(200.0, 0.2, 200.0)),
+            Transform::from_xyz(0.0, 0.0, 0.0),
+        ),
+        (
+            GroundCollider::new(Vec3::splat(2.0)),
+            Transform::from_xyz(0.0, 1.0, 0.0),
+        ),
+    ])
+}
+
+#[test]
+fn ray_hits_the_top_of_a_box() {
+    let ground = floor_and_cube();
+
+    let hit = ground.raycast_down(Vec3::new(5.0, 3.0, 5.0), 10.0).unwrap();
+
+    assert!(hit.point.distance(Vec3::new(5.0, 0.1, 5.0)) <= EPSILON);
+    assert!(hit.normal.distance(Vec3::Y) <= EPSILON);
+    assert!((hit.distance - 2.9).abs() <= EPSILON);
+}
+
+#[test]
+fn ray_hits_the_closest_box() {
+    let ground = floor_and_cube();
+
+    let hit = ground
+        .raycast_down(Vec3::new(0.5, 3.0, -0.5), 10.0)
+        .unwrap();
+
+    assert!(hit.point.distance(Vec3::new(0.5, 2.0, -0.5)) <= EPSILON);
+}
+
+#[test]
+fn ray_misses_when_out_of_range_or_beside_the_boxes() {
+    let ground = floor_and_cube();
+
+    assert_eq!(ground.raycast_down(Vec3::new(5.0, 3.0, 5.0), 1.0), None);
+    assert_eq!(ground.raycast_down(Vec3::new(500.0, 3.0, 0.0), 10.0), None);
+    // The ray starts inside the cube
+    assert_eq!(
+        ground
+            .raycast_down(Vec3::new(0.0, 1.0, 0.0), 10.0)
+            .map(|hit| hit.point.y < 0.5),
+        Some(true)
+    );
+}
+
+#[test]
+fn ray_hits_rotated_boxes() {
+    let transform = Transform::from_xyz(0.0, 0.0, 0.0).with_rotation(Quat::from_rotation_z(0.5));
+    let ground = ground_with_colliders(vec![(
+        GroundCollider::new(Vec3::new(10.0, 1.0, 10.0)),
+        transform,
+    )]);
+
+    let hit = ground.raycast_down(Vec3::new(0.0, 5.0, 0.0), 10.0).unwrap();
+
+    let expected_normal = transform.rotation * Vec3::Y;
+    assert!(hit.normal.distance(expected_normal) <= EPSILON);
+    assert!((hit.point.y - 0.5 / 0.5f32.cos()).abs() <= EPSILON);
+}
+
+#[test]
+fn feet_are_placed_on_the_ground_or_left_where_they_are() {
+    let ground = floor_and_cube();
+
+    let on_cube = ground.place_on_ground(Vec3::new(0.5, 0.5, 0.5));
+    assert!(on_cube.distance(Vec3::new(0.5, 2.0, 0.5)) <= EPSILON);
+
+    let on_floor = ground.place_on_ground(Vec3::new(5.0, 0.5, 5.0));
+    assert!(on_floor.distance(Vec3::new(5.0, 0.1, 5.0)) <= EPSILON);
+
+    let nothing_below = Vec3::new(500.0, 0.5, 0.0);
+    assert_eq!(ground.place_on_ground(nothing_below), nothing_below);
+}
+
+/// A flat plane at a fixed height, to check that custom ground queries are used
+struct Plane {
+    height: f32,
+}
+
+impl GroundQuery for Plane {
+    fn raycast_down(&self, origin: Vec3, max_distance: f32) -> Option<GroundHit> {
+        let distance = origin.y - self.height;
+
+        (0.0..=max_distance)
+            .contains(&distance)
+            .then_some(GroundHit {
+                point: Vec3::new(origin.x, self.height, origin.z),
+                normal: Vec3::Y,
+                distance,
+            })
+    }
+}
+
+#[test]
+fn extra_ground_queries_are_included() {
+    let mut ground = floor_and_cube();
+    ground.add_query(Plane { height: 0.8 });
+
+    let hit = ground.raycast_down(Vec3::new(5.0, 3.0, 5.0), 10.0).unwrap();
+    assert!((hit.point.y - 0.8).abs() <= EPSILON);
+
+    let hit = ground.raycast_down(Vec3::new(0.0, 3.0, 0.0), 10.0).unwrap();
+    assert!((hit.point.y - 2.0).abs() <= EPSILON);
+}
@@ -0,0 +1,726 @@
+use std::f32::consts::PI;
+
+use glam::{Quat, Vec3};
+use proptest::prelude::*;
+use walking_ik_spider::{
+    ik::{
+        solver::{
+            ccd::CcdSolver, fabrik::FabrikSolver, jacobian::JacobianSolver, ChainSolver, IkSolver,
+        },
+        tree::IkTree,
+        IkChain, IkError, JointConstraint, PoleTarget, SolveSettings,
+    },
+    rotations,
+};
+
+const EPSILON: f32 = 1e-3;
+
+const SOLVERS: [ChainSolver; 3] = [
+    ChainSolver::Fabrik(FabrikSolver),
+    ChainSolver::Ccd(CcdSolver),
+    ChainSolver::Jacobian(JacobianSolver { damping: 0.5 }),
+];
+
+/// Enough iterations for every solver to converge on the targets used here
+const SETTINGS: SolveSettings = SolveSettings {
+    tolerance: 0.01,
+    max_iterations: 500,
+};
+
+fn leg_chain() -> IkChain {
+    let points = vec![
+        Vec3::ZERO,
+        Vec3::new(1.0, 3.0, 0.0),
+        Vec3::new(2.0, 0.6, 0.0),
+        Vec3::new(2.0, 0.0, 0.0),
+    ];
+
+    IkChain::new(points).unwrap().with_settings(SETTINGS)
+}
+
+fn segment_lengths(chain: &IkChain) -> Vec<f32> {
+    chain
+        .points()
+        .windows(2)
+        .map(|segment| segment[0].distance(segment[1]))
+        .collect()
+}
+
+fn assert_lengths_kept(chain: &IkChain, lengths: &[f32]) {
+    for (index, (length, expected)) in segment_lengths(chain).iter().zip(lengths).enumerate() {
+        assert!(
+            (length - expected).abs() <= EPSILON * expected.max(1.0),
+            "segment {} has length {}, expected {}",
+            index,
+            length,
+            expected
+        );
+    }
+}
+
+fn assert_attached_to_start(chain: &IkChain) {
+    let distance = chain.points()[0].distance(chain.start);
+    assert!(
+        distance <= EPSILON,
+        "first point is {} from the start",
+        distance
+    );
+}
+
+/// The direction of every segment, the first one included
+fn segment_directions(chain: &IkChain) -> Vec<Vec3> {
+    chain
+        .points()
+        .windows(2)
+        .map(|segment| (segment[1] - segment[0]).normalize())
+        .collect()
+}
+
+// Unit tests
+
+#[test]
+fn every_solver_keeps_lengths_and_start() {
+    let targets = [
+        Vec3::new(3.0, -0.5, 1.0),
+        Vec3::new(-1.0, 2.0, 2.0),
+        Vec3::new(0.5, 0.5, 0.5),
+        Vec3::new(20.0, 0.0, 0.0),
+    ];
+
+    for solver in SOLVERS {
+        for target in targets {
+            let mut chain = leg_chain();
+            let lengths = segment_lengths(&chain);
+
+            solver.solve(&mut chain, target).unwrap();
+
+            assert_lengths_kept(&chain, &lengths);
+            assert_attached_to_start(&chain);
+        }
+    }
+}
+
+#[test]
+fn every_solver_reaches_reachable_targets() {
+    let targets = [
+        Vec3::new(3.0, -0.5, 1.0),
+        Vec3::new(-1.0, 2.0, 2.0),
+        Vec3::new(2.0, 4.0, -1.0),
+    ];
+
+    for solver in SOLVERS {
+        for target in targets {
+            let mut chain = leg_chain();
+            let report = solver.solve(&mut chain, target).unwrap();
+
+            assert!(report.reachable);
+            assert!(
+                chain.end().distance(target) <= SETTINGS.tolerance,
+                "{:?} ended {} from {}",
+                solver,
+                chain.end().distance(target),
+                target
+            );
+            assert_eq!(report.error, chain.end().distance(target));
+        }
+    }
+}
+
+#[test]
+fn unreachable_target_straightens_the_chain() {
+    let mut chain = leg_chain();
+    let target = Vec3::new(0.0, 0.0, -20.0);
+
+    let report = FabrikSolver.solve(&mut chain, target).unwrap();
+
+    assert!(!report.reachable);
+    assert!((report.overshoot - (20.0 - chain.total_length())).abs() <= EPSILON);
+    for direction in segment_directions(&chain) {
+        assert!(direction.distance(Vec3::NEG_Z) <= EPSILON);
+    }
+}
+
+#[test]
+fn unreachable_target_keeps_joints_within_their_constraints() {
+    // Like a spider hip, the knee may never bend exactly straight
+    let hinge = JointConstraint::Hinge {
+        axis: Vec3::X,
+        min_angle: 0.01,
+        max_angle: PI,
+    };
+    let target = Vec3::new(3.0, -20.0, 4.0);
+
+    for solver in SOLVERS {
+        let mut chain = leg_chain().with_constraints(vec![hinge]).unwrap();
+
+        let report = solver.solve(&mut chain, target).unwrap();
+
+        // The first joint is constrained relative to the direction from the start to the target
+        let reference = rotations::looking_at(chain.start, target, Vec3::Y);
+        let local_direction = reference.inverse() * segment_directions(&chain)[0];
+        let angle = Vec3::X
+            .dot(Vec3::NEG_Z.cross(local_direction))
+            .atan2(Vec3::NEG_Z.dot(local_direction));
+
+        assert!(!report.reachable);
+        assert!(angle >= 0.01 - EPSILON, "first joint bent {}", angle);
+        assert!((report.error - chain.end().distance(target)).abs() <= EPSILON);
+    }
+}
+
+#[test]
+fn target_closer_than_the_folded_chain_is_unreachable() {
+    // The foot points down onto the start, where the two leg segments can't fold back to
+    let target = Vec3::new(0.0, -0.6, 0.0);
+    let min_reach = 10.0f32.sqrt() - 6.76f32.sqrt();
+
+    for solver in SOLVERS {
+        let mut chain =
+            leg_chain().with_end_orientation(Quat::from_rotation_arc(Vec3::NEG_Z, Vec3::NEG_Y));
+
+        let report = solver.solve(&mut chain, target).unwrap();
+
+        assert!(!report.reachable);
+        assert!((report.overshoot - min_reach).abs() <= EPSILON);
+        assert_attached_to_start(&chain);
+    }
+}
+
+#[test]
+fn solved_chain_is_not_iterated_again() {
+    let mut chain = leg_chain();
+    let target = Vec3::new(3.0, -0.5, 1.0);
+
+    FabrikSolver.solve(&mut chain, target).unwrap();
+    let report = FabrikSolver.solve(&mut chain, target).unwrap();
+
+    assert_eq!(report.iterations, 0);
+}
+
+#[test]
+fn non_finite_input_is_an_error() {
+    let mut chain = leg_chain();
+
+    assert_eq!(
+        FabrikSolver.solve(&mut chain, Vec3::new(f32::NAN, 0.0, 0.0)),
+        Err(IkError::NonFiniteInput)
+    );
+    assert_eq!(
+        IkChain::new(vec![Vec3::ZERO, Vec3::INFINITY]).err(),
+        Some(IkError::NonFiniteInput)
+    );
+}
+
+#[test]
+fn invalid_pole_or_end_orientation_is_an_error() {
+    let target = Vec3::new(3.0, -0.5, 1.0);
+    let chains = [
+        leg_chain().with_end_orientation(Quat::from_xyzw(0.0, 0.0, 0.0, 0.0)),
+        leg_chain().with_end_orientation(Quat::from_xyzw(f32::NAN, 0.0, 0.0, 1.0)),
+        leg_chain().with_pole(PoleTarget::Direction(Vec3::new(0.0, f32::INFINITY, 0.0))),
+        leg_chain().with_pole(PoleTarget::Point(Vec3::NAN)),
+    ];
+
+    for mut chain in chains {
+        for solver in SOLVERS {
+            assert_eq!(
+                solver.solve(&mut chain, target),
+                Err(IkError::NonFiniteInput)
+            );
+        }
+    }
+}
+
+#[test]
+fn invalid_chains_are_rejected() {
+    assert_eq!(
+        IkChain::new(vec![Vec3::ZERO]).err(),
+        Some(IkError::TooFewPoints { count: 1 })
+    );
+    assert_eq!(
+        IkChain::new(vec![Vec3::ZERO, Vec3::X, Vec3::X]).err(),
+        Some(IkError::DegenerateSegment { index: 1 })
+    );
+    assert_eq!(
+        leg_chain().get_segment(3).err(),
+        Some(IkError::IndexOutOfRange { index: 3, len: 3 })
+    );
+}
+
+#[test]
+fn changing_a_segment_length_moves_the_points_after_it() {
+    let mut chain = leg_chain();
+    let points = chain.points().to_vec();
+
+    chain.set_segment_length(1, 5.2).unwrap();
+
+    let offset = (points[2] - points[1]).normalize() * 2.6;
+    assert_eq!(&chain.points()[..2], &points[..2]);
+    assert!(chain.points()[2].distance(points[2] + offset) <= EPSILON);
+    assert!(chain.points()[3].distance(points[3] + offset) <= EPSILON);
+    assert_eq!(chain.segment_lengths()[1], 5.2);
+}
+
+#[test]
+fn solving_keeps_changed_segment_lengths() {
+    for solver in SOLVERS {
+        let mut chain = leg_chain();
+        chain.set_segment_length(0, 1.5).unwrap();
+        chain.set_segment_length(2, 1.0).unwrap();
+        let lengths = chain.segment_lengths().to_vec();
+
+        solver.solve(&mut chain, Vec3::new(2.0, 1.0, 1.0)).unwrap();
+
+        assert_lengths_kept(&chain, &lengths);
+        assert_attached_to_start(&chain);
+    }
+}
+
+#[test]
+fn invalid_segment_lengths_are_rejected() {
+    let mut chain = leg_chain();
+
+    assert_eq!(
+        chain.set_segment_length(3, 1.0),
+        Err(IkError::IndexOutOfRange { index: 3, len: 3 })
+    );
+    assert_eq!(
+        chain.set_segment_length(1, 0.0),
+        Err(IkError::DegenerateSegment { index: 1 })
+    );
+    assert_eq!(
+        chain.set_segment_length(1, f32::NAN),
+        Err(IkError::NonFiniteInput)
+    );
+}
+
+#[test]
+fn segment_orientations_look_along_their_segments() {
+    for solver in SOLVERS {
+        let mut chain = leg_chain();
+        solver.solve(&mut chain, Vec3::new(-1.0, 2.0, 2.0)).unwrap();
+
+        for (index, direction) in segment_directions(&chain).into_iter().enumerate() {
+            let segment = chain.get_segment(index).unwrap();
+            assert!((segment.orientation * Vec3::NEG_Z).distance(direction) <= EPSILON);
+        }
+    }
+}
+
+#[test]
+fn oriented_last_segment_takes_the_end_orientation() {
+    let end_orientation = Quat::from_rotation_x(-1.2) * Quat::from_rotation_z(0.5);
+    let mut chain = leg_chain().with_end_orientation(end_orientation);
+
+    FabrikSolver
+        .solve(&mut chain, Vec3::new(3.0, -0.5, 1.0))
+        .unwrap();
+
+    let foot = chain.get_segment(2).unwrap();
+    assert!(foot.orientation.angle_between(end_orientation) <= EPSILON);
+}
+
+#[test]
+fn segment_orientations_dont_flip_when_a_segment_turns_vertical() {
+    let points = vec![
+        Vec3::ZERO,
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(2.0, -2.0, 0.0),
+    ];
+    let mut chain = IkChain::new(points).unwrap().with_settings(SETTINGS);
+    let mut previous: Option<Vec<Quat>> = None;
+
+    // The second segment swings through pointing straight down on the way
+    for step in 0..=40 {
+        let target = Vec3::new(3.5 - step as f32 * 0.05, -1.5, 0.1);
+        FabrikSolver.solve(&mut chain, target).unwrap();
+
+        let orientations: Vec<Quat> = (0..2)
+            .map(|index| chain.get_segment(index).unwrap().orientation)
+            .collect();
+
+        if let Some(previous) = previous {
+            for (orientation, previous) in orientations.iter().zip(previous) {
+                assert!(orientation.angle_between(previous) <= 0.3);
+            }
+        }
+
+        previous = Some(orientations);
+    }
+}
+
+#[test]
+fn segment_orientations_dont_flip_when_the_chain_passes_through_vertical() {
+    let points = vec![
+        Vec3::ZERO,
+        Vec3::new(1.0, 1.5, 0.0),
+        Vec3::new(1.0, 3.0, 0.0),
+    ];
+    let mut chain = IkChain::new(points).unwrap().with_settings(SETTINGS);
+    let mut previous: Option<Vec<Quat>> = None;
+
+    // The end passes straight over the start, where the line from the start to the end is vertical
+    for step in 0..=40 {
+        let offset = 1.0 - step as f32 * 0.05;
+        let target = Vec3::new(offset, 2.5, 0.3 * offset);
+        FabrikSolver.solve(&mut chain, target).unwrap();
+
+        let orientations: Vec<Quat> = (0..2)
+            .map(|index| chain.get_segment(index).unwrap().orientation)
+            .collect();
+
+        if let Some(previous) = previous {
+            for (index, (orientation, previous)) in orientations.iter().zip(previous).enumerate() {
+                let change = orientation.angle_between(previous);
+                assert!(
+                    change <= 0.3,
+                    "segment {} turned {} in step {}",
+                    index,
+                    change,
+                    step
+                );
+            }
+        }
+
+        previous = Some(orientations);
+    }
+}
+
+#[test]
+fn cone_constrained_joints_stay_within_their_cone() {
+    let max_angle = 0.4;
+
+    for solver in SOLVERS {
+        let mut chain = leg_chain()
+            .with_constraints(vec![JointConstraint::Cone { max_angle }; 3])
+            .unwrap();
+        let lengths = segment_lengths(&chain);
+        let target = Vec3::new(-1.0, 2.0, 2.0);
+
+        solver.solve(&mut chain, target).unwrap();
+
+        // The first joint is constrained relative to the direction towards the target, the others to the previous segment
+        let directions = segment_directions(&chain);
+        assert!(directions[0].angle_between(target.normalize()) <= max_angle + EPSILON);
+        for pair in directions.windows(2) {
+            assert!(pair[0].angle_between(pair[1]) <= max_angle + EPSILON);
+        }
+
+        assert_lengths_kept(&chain, &lengths);
+        assert_attached_to_start(&chain);
+    }
+}
+
+#[test]
+fn hinge_constrained_chain_stays_in_its_plane() {
+    let hinge = JointConstraint::Hinge {
+        axis: Vec3::X,
+        min_angle: -PI,
+        max_angle: PI,
+    };
+
+    for solver in SOLVERS {
+        let mut chain = leg_chain().with_constraints(vec![hinge; 3]).unwrap();
+        let target = Vec3::new(-1.0, 2.0, 2.0);
+
+        solver.solve(&mut chain, target).unwrap();
+
+        // Hinges around the local x axis only bend in the upright plane through the start and the target
+        let normal = target.cross(Vec3::Y).normalize();
+        for point in chain.points() {
+            assert!(normal.dot(*point - chain.start).abs() <= EPSILON);
+        }
+    }
+}
+
+/// A trunk standing up from the origin with two arms of two segments reaching out left and right from its top
+fn forked_tree() -> IkTree {
+    let mut tree = IkTree::new(Vec3::ZERO).with_settings(SETTINGS);
+    let trunk = tree
+        .add_branch(None, vec![Vec3::new(0.0, 2.0, 0.0)])
+        .unwrap();
+    tree.add_branch(
+        Some(trunk),
+        vec![Vec3::new(1.0, 2.0, 0.0), Vec3::new(2.0, 2.0, 0.0)],
+    )
+    .unwrap();
+    tree.add_branch(
+        Some(trunk),
+        vec![Vec3::new(-1.0, 2.0, 0.0), Vec3::new(-2.0, 2.0, 0.0)],
+    )
+    .unwrap();
+    tree
+}
+
+fn branch_lengths(tree: &IkTree) -> Vec<Vec<f32>> {
+    tree.branches()
+        .iter()
+        .map(|branch| {
+            branch
+                .points()
+                .windows(2)
+                .map(|segment| segment[0].distance(segment[1]))
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn tree_solving_keeps_lengths_and_stays_attached() {
+    let targets = [
+        (Vec3::new(1.5, 3.0, 0.5), Vec3::new(-1.5, 1.5, -0.5)),
+        (Vec3::new(3.0, 0.0, 2.0), Vec3::new(-0.5, 4.0, 0.0)),
+        (Vec3::new(20.0, 5.0, 0.0), Vec3::new(-3.0, -8.0, 1.0)),
+    ];
+
+    for (left, right) in targets {
+        let mut tree = forked_tree();
+        let lengths = branch_lengths(&tree);
+        tree.set_target(1, Some(left)).unwrap();
+        tree.set_target(2, Some(right)).unwrap();
+
+        tree.solve().unwrap();
+
+        for (solved, expected) in branch_lengths(&tree).iter().zip(&lengths) {
+            for (length, expected) in solved.iter().zip(expected) {
+                assert!((length - expected).abs() <= EPSILON);
+            }
+        }
+
+        assert!(tree.branch(0).unwrap().points()[0].distance(tree.root) <= EPSILON);
+        // Branches start where their parent ends
+        let junction = tree.branch(0).unwrap().end();
+        for arm in 1..=2 {
+            assert!(tree.branch(arm).unwrap().points()[0].distance(junction) <= EPSILON);
+        }
+    }
+}
+
+#[test]
+fn tree_reaches_two_reachable_targets() {
+    let mut tree = forked_tree().with_settings(SolveSettings {
+        tolerance: 0.01,
+        max_iterations: 100,
+    });
+    let (left, right) = (Vec3::new(1.5, 3.0, 0.5), Vec3::new(-1.5, 1.5, -0.5));
+    tree.set_target(1, Some(left)).unwrap();
+    tree.set_target(2, Some(right)).unwrap();
+
+    let report = tree.solve().unwrap();
+
+    assert!(report.reachable);
+    assert!(report.error <= SETTINGS.tolerance);
+    assert!(tree.branch(1).unwrap().end().distance(left) <= SETTINGS.tolerance);
+    assert!(tree.branch(2).unwrap().end().distance(right) <= SETTINGS.tolerance);
+}
+
+#[test]
+fn tree_junction_is_placed_at_the_average_of_its_branches() {
+    let mut tree = IkTree::new(Vec3::ZERO).with_settings(SolveSettings {
+        tolerance: 0.0,
+        max_iterations: 1,
+    });
+    let trunk = tree
+        .add_branch(None, vec![Vec3::new(0.0, 1.0, 0.0)])
+        .unwrap();
+    let left = tree
+        .add_branch(Some(trunk), vec![Vec3::new(1.0, 1.0, 0.0)])
+        .unwrap();
+    let right = tree
+        .add_branch(Some(trunk), vec![Vec3::new(-1.0, 1.0, 0.0)])
+        .unwrap();
+    let (left_target, right_target) = (Vec3::new(1.0, 2.0, 0.0), Vec3::new(-0.5, 1.0, 1.0));
+    tree.set_target(left, Some(left_target)).unwrap();
+    tree.set_target(right, Some(right_target)).unwrap();
+
+    tree.solve().unwrap();
+
+    // The backward pass puts each arm on its target and pulls its start back along the arm,
+    // the junction goes to the average of those starts and the forward pass hangs it back off the root
+    let junction = Vec3::new(0.0, 1.0, 0.0);
+    let left_start = left_target + (junction - left_target).normalize();
+    let right_start = right_target + (junction - right_target).normalize();
+    let average = (left_start + right_start) / 2.0;
+
+    let solved_junction = tree.branch(trunk).unwrap().end();
+    assert!(solved_junction.distance(average.normalize()) <= EPSILON);
+}
+
+#[test]
+fn tree_stretches_straight_towards_an_unreachable_target() {
+    let mut tree = IkTree::new(Vec3::ZERO).with_settings(SETTINGS);
+    let trunk = tree
+        .add_branch(
+            None,
+            vec![Vec3::new(0.0, 1.0, 0.0), Vec3::new(1.0, 2.0, 0.0)],
+        )
+        .unwrap();
+    let arm = tree
+        .add_branch(
+            Some(trunk),
+            vec![Vec3::new(2.0, 1.0, 0.0), Vec3::new(3.0, 2.0, 0.0)],
+        )
+        .unwrap();
+    let target = Vec3::new(-10.0, 3.0, 6.0);
+    tree.set_target(arm, Some(target)).unwrap();
+
+    let report = tree.solve().unwrap();
+
+    assert!(!report.reachable);
+    assert!(report.overshoot > 0.0);
+
+    let direction = target.normalize();
+    for branch in tree.branches() {
+        for point in branch.points() {
+            assert!(point.cross(direction).length() <= EPSILON);
+            assert!(point.dot(direction) >= -EPSILON);
+        }
+    }
+}
+
+// Property tests
+
+prop_compose! {
+    fn arbitrary_vec3(range: f32)(x in -range..range, y in -range..range, z in -range..range) -> Vec3 {
+        Vec3::new(x, y, z)
+    }
+}
+
+prop_compose! {
+    /// A chain of 2 to 6 segments with random directions and lengths
+    fn arbitrary_chain()(
+        start in arbitrary_vec3(10.0),
+        segments in prop::collection::vec((arbitrary_vec3(1.0), 0.2f32..3.0), 2..=6),
+    ) -> IkChain {
+        let mut points = vec![start];
+        for (direction, length) in segments {
+            let direction = direction.try_normalize().unwrap_or(Vec3::Y);
+            points.push(*points.last().unwrap() + direction * length);
+        }
+
+        IkChain::new(points).unwrap().with_settings(SETTINGS)
+    }
+}
+
+prop_compose! {
+    /// A direction and a fraction of the way between the closest and the farthest point the chain can reach
+    fn arbitrary_target_placement()(direction in arbitrary_vec3(1.0), fraction in 0.0f32..1.0) -> (Vec3, f32) {
+        (direction.try_normalize().unwrap_or(Vec3::X), fraction)
+    }
+}
+
+/// The closest a chain can get to its own start, the longest segment folded back over all the others
+fn min_reach(lengths: &[f32]) -> f32 {
+    let longest = lengths.iter().copied().fold(0.0, f32::max);
+    (2.0 * longest - lengths.iter().sum::<f32>()).max(0.0)
+}
+
+fn solver_strategy() -> impl Strategy<Value = ChainSolver> {
+    prop::sample::select(SOLVERS.to_vec())
+}
+
+proptest! {
+    #[test]
+    fn solving_keeps_lengths_and_start(
+        mut chain in arbitrary_chain(),
+        target in arbitrary_vec3(30.0),
+        solver in solver_strategy(),
+    ) {
+        let lengths = segment_lengths(&chain);
+
+        let report = solver.solve(&mut chain, target).unwrap();
+
+        assert_lengths_kept(&chain, &lengths);
+        assert_attached_to_start(&chain);
+        prop_assert!(report.error.is_finite());
+        prop_assert_eq!(report.reachable, report.overshoot <= 0.0);
+    }
+
+    #[test]
+    fn fabrik_reaches_reachable_targets(
+        mut chain in arbitrary_chain(),
+        (direction, fraction) in arbitrary_target_placement(),
+    ) {
+        // Stay a little away from the edges of the reachable area, where convergence gets slow
+        let lengths = segment_lengths(&chain);
+        let min_reach = min_reach(&lengths) + 0.1;
+        let max_reach = chain.total_length() * 0.95;
+        prop_assume!(min_reach < max_reach);
+
+        let target = chain.start + direction * (min_reach + (max_reach - min_reach) * fraction);
+        let report = FabrikSolver.solve(&mut chain, target).unwrap();
+
+        prop_assert!(report.reachable);
+        prop_assert!(
+            chain.end().distance(target) <= SETTINGS.tolerance,
+            "ended {} from the target after {} iterations",
+            chain.end().distance(target),
+            report.iterations
+        );
+    }
+
+    #[test]
+    fn unreachable_targets_are_reported(
+        mut chain in arbitrary_chain(),
+        direction in arbitrary_vec3(1.0),
+        extra_distance in 0.1f32..20.0,
+    ) {
+        let direction = direction.try_normalize().unwrap_or(Vec3::X);
+        let target = chain.start + direction * (chain.total_length() + extra_distance);
+
+        let report = FabrikSolver.solve(&mut chain, target).unwrap();
+
+        prop_assert!(!report.reachable);
+        prop_assert!((report.overshoot - extra_distance).abs() <= EPSILON * 10.0);
+        prop_assert!((report.error - extra_distance).abs() <= EPSILON * 10.0);
+    }
+
+    #[test]
+    fn too_close_targets_are_reported(
+        mut chain in arbitrary_chain(),
+        direction in arbitrary_vec3(1.0),
+        fraction in 0.0f32..0.9,
+    ) {
+        let min_reach = min_reach(&segment_lengths(&chain));
+        prop_assume!(min_reach > 0.1);
+
+        let direction = direction.try_normalize().unwrap_or(Vec3::X);
+        let target = chain.start + direction * min_reach * fraction;
+
+        let report = FabrikSolver.solve(&mut chain, target).unwrap();
+
+        prop_assert!(!report.reachable);
+        prop_assert!((report.overshoot - min_reach * (1.0 - fraction)).abs() <= EPSILON * 10.0);
+    }
+
+    #[test]
+    fn cone_constraint_stays_within_its_angle(
+        direction in arbitrary_vec3(1.0),
+        max_angle in 0.0f32..PI,
+    ) {
+        prop_assume!(direction.length_squared() > 1e-4);
+
+        let constrained = JointConstraint::Cone { max_angle }.constrain(direction);
+
+        prop_assert!((constrained.length() - 1.0).abs() <= EPSILON);
+        prop_assert!(constrained.angle_between(Vec3::NEG_Z) <= max_angle + EPSILON);
+    }
+
+    #[test]
+    fn hinge_constraint_stays_on_its_plane_and_within_its_angles(
+        direction in arbitrary_vec3(1.0),
+        axis in prop::sample::select(vec![Vec3::X, Vec3::Y, Vec3::new(1.0, 1.0, 0.0).normalize()]),
+        min_angle in -PI..0.0f32,
+        max_angle in 0.0f32..PI,
+    ) {
+        let constrained = JointConstraint::Hinge { axis, min_angle, max_angle }.constrain(direction);
+
+        // The angle is measured from negative z flattened onto the plane of the hinge
+        let forward = (Vec3::NEG_Z - axis * axis.dot(Vec3::NEG_Z)).normalize();
+        let angle = axis.dot(forward.cross(constrained)).atan2(forward.dot(constrained));
+
+        prop_assert!((constrained.length() - 1.0).abs() <= EPSILON);
+        prop_assert!(axis.dot(constrained).abs() <= EPSILON);
+        prop_assert!(angle >= min_angle - EPSILON && angle <= max_angle + EPSILON);
+    }
+}
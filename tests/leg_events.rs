@@ -0,0 +1,167 @@
+#![cfg(feature = "bevy")]
+
+mod common;
+
+use bevy::prelude::*;
+use common::{leg_chain, TestWorld};
+use walking_ik_spider::plugin::leg::{
+    animate_leg_towards_target, update_leg_phases, AnimatedLeg, LegPhase, LegStepLanded,
+    LegStepStarted, StepPlan,
+};
+
+struct LegWorld {
+    test: TestWorld,
+    leg: Entity,
+    body: Entity,
+}
+
+impl LegWorld {
+    fn new() -> Self {
+        let mut test = TestWorld::new()
+            .with_event::<LegStepStarted>()
+            .with_event::<LegStepLanded>()
+            .with_systems((animate_leg_towards_target, update_leg_phases).chain());
+
+        let leg = test
+            .world
+            .spawn((
+                leg_chain(),
+                AnimatedLeg::new(Vec3::ZERO, Vec3::new(2.0, 0.0, 0.0)),
+            ))
+            .id();
+        let body = test.world.spawn_empty().push_children(&[leg]).id();
+
+        LegWorld { test, leg, body }
+    }
+
+    fn leg(&mut self) -> Mut<'_, AnimatedLeg> {
+        self.test.get_mut::<AnimatedLeg>(self.leg)
+    }
+
+    fn elapsed_seconds(&self) -> f32 {
+        self.test.world.resource::<Time>().elapsed_seconds()
+    }
+}
+
+#[test]
+fn planted_legs_send_no_events() {
+    let mut world = LegWorld::new();
+
+    world.test.update(0.1);
+
+    assert_eq!(world.leg().phase(), LegPhase::Planted);
+    assert!(world.test.drain_events::<LegStepStarted>().is_empty());
+    assert!(world.test.drain_events::<LegStepLanded>().is_empty());
+}
+
+#[test]
+fn a_step_goes_through_every_phase_and_records_when_each_started() {
+    let mut world = LegWorld::new();
+    world.leg().start_step(StepPlan {
+        target: Vec3::new(2.0, 0.0, 1.0),
+        duration: 1.0,
+        ground_normal: None,
+    });
+
+    // Each update lands the step somewhere inside the next phase
+    for (seconds, phase) in [
+        (0.1, LegPhase::Lifting),
+        (0.3, LegPhase::Swinging),
+        (0.5, LegPhase::Landing),
+        (0.2, LegPhase::Planted),
+    ] {
+        world.test.update(seconds);
+        let elapsed_seconds = world.elapsed_seconds();
+
+        assert_eq!(world.leg().phase(), phase);
+        assert_eq!(world.leg().phase_started_at(), elapsed_seconds);
+    }
+
+    let phase_started_at = world.leg().phase_started_at();
+    world.test.update(0.5);
+    let elapsed_seconds = world.elapsed_seconds();
+
+    assert_eq!(world.leg().phase_started_at(), phase_started_at);
+    assert!((world.leg().time_in_phase(elapsed_seconds) - 0.5).abs() <= 1e-4);
+}
+
+#[test]
+fn a_step_sends_started_then_landed() {
+    let mut world = LegWorld::new();
+    let end = Vec3::new(2.0, 0.0, 1.0);
+
+    world.leg().set_new_target(end);
+    world.test.update(0.01);
+
+    let started = world.test.drain_events::<LegStepStarted>();
+    assert_eq!(started.len(), 1);
+    assert_eq!(started[0].leg, world.leg);
+    assert_eq!(started[0].body, Some(world.body));
+    assert_eq!(started[0].start, Vec3::new(2.0, 0.0, 0.0));
+    assert_eq!(started[0].end, end);
+    assert_eq!(world.leg().phase(), LegPhase::Lifting);
+    assert!(world.test.drain_events::<LegStepLanded>().is_empty());
+
+    world.test.update(1.0);
+
+    let landed = world.test.drain_events::<LegStepLanded>();
+    assert_eq!(landed.len(), 1);
+    assert_eq!(landed[0].end, end);
+    assert_eq!(world.leg().phase(), LegPhase::Planted);
+    assert!(world.test.drain_events::<LegStepStarted>().is_empty());
+}
+
+#[test]
+fn a_new_target_mid_step_starts_a_new_step() {
+    let mut world = LegWorld::new();
+
+    world.leg().set_new_target(Vec3::new(2.0, 0.0, 1.0));
+    world.test.update(0.01);
+    world.test.drain_events::<LegStepStarted>();
+
+    world.leg().set_new_target(Vec3::new(2.0, 0.0, 2.0));
+    world.test.update(0.01);
+
+    assert_eq!(world.test.drain_events::<LegStepStarted>().len(), 1);
+    assert!(world.test.drain_events::<LegStepLanded>().is_empty());
+}
+
+#[test]
+fn every_new_target_between_updates_sends_a_started_event() {
+    let mut world = LegWorld::new();
+    let (first, second) = (Vec3::new(2.0, 0.0, 1.0), Vec3::new(2.0, 0.0, 2.0));
+
+    world.leg().set_new_target(first);
+    world.leg().set_new_target(second);
+    world.test.update(0.01);
+
+    let started = world.test.drain_events::<LegStepStarted>();
+    assert_eq!(started.len(), 2);
+    assert_eq!(
+        (started[0].start, started[0].end),
+        (Vec3::new(2.0, 0.0, 0.0), first)
+    );
+    assert_eq!((started[1].start, started[1].end), (first, second));
+
+    world.test.update(1.0);
+
+    // Only the second step gets to land
+    let landed = world.test.drain_events::<LegStepLanded>();
+    assert_eq!(landed.len(), 1);
+    assert_eq!(landed[0].end, second);
+    assert!(world.test.drain_events::<LegStepStarted>().is_empty());
+}
+
+#[test]
+fn only_the_latest_steps_between_updates_are_reported() {
+    let mut world = LegWorld::new();
+
+    for step in 1..=100 {
+        world.leg().set_new_target(Vec3::new(2.0, 0.0, step as f32));
+    }
+    world.test.update(0.01);
+
+    let started = world.test.drain_events::<LegStepStarted>();
+    assert!(!started.is_empty() && started.len() < 100);
+    assert_eq!(started.last().unwrap().end, Vec3::new(2.0, 0.0, 100.0));
+}
@@ -0,0 +1,148 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use glam::{Quat, Vec3};
+use walking_ik_spider::rotations::{
+    angle_between_on_plane, clamp_rotation_to_cone, clamp_to_cone, clamp_twist, looking_towards,
+    project_on_plane, swing_twist,
+};
+
+const EPSILON: f32 = 1e-4;
+
+fn assert_same_rotation(a: Quat, b: Quat) {
+    assert!(a.angle_between(b) <= EPSILON * 10.0, "{:?} != {:?}", a, b);
+}
+
+fn assert_same_vector(a: Vec3, b: Vec3) {
+    assert!(a.distance(b) <= EPSILON, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn looking_towards_points_negative_z_along_the_direction() {
+    let direction = Vec3::new(1.0, -2.0, 0.5);
+    let orientation = looking_towards(direction, Vec3::Y);
+
+    assert_same_vector(orientation * Vec3::NEG_Z, direction.normalize());
+    assert!((orientation * Vec3::Y).dot(Vec3::Y) > 0.0);
+}
+
+#[test]
+fn project_on_plane_removes_the_normal_part() {
+    assert_same_vector(
+        project_on_plane(Vec3::new(1.0, 2.0, 3.0), Vec3::Y),
+        Vec3::new(1.0, 0.0, 3.0),
+    );
+}
+
+#[test]
+fn swing_twist_recombines_into_the_rotation() {
+    let rotation =
+        Quat::from_rotation_x(0.7) * Quat::from_rotation_z(-1.1) * Quat::from_rotation_y(2.0);
+    let axis = Vec3::new(0.3, 1.0, -0.2).normalize();
+
+    let (swing, twist) = swing_twist(rotation, axis);
+
+    assert_same_rotation(swing * twist, rotation);
+    // The twist only turns around the axis, and the swing doesn't turn around it at all
+    assert_same_vector(twist * axis, axis);
+    let (swing_axis, _) = swing.to_axis_angle();
+    assert!(swing_axis.dot(axis).abs() <= EPSILON);
+}
+
+#[test]
+fn swing_twist_of_a_pure_twist_has_no_swing() {
+    let rotation = Quat::from_rotation_z(1.3);
+
+    let (swing, twist) = swing_twist(rotation, Vec3::Z);
+
+    assert_same_rotation(swing, Quat::IDENTITY);
+    assert_same_rotation(twist, rotation);
+}
+
+#[test]
+fn swing_twist_of_a_half_turn_swing_has_no_twist() {
+    let rotation = Quat::from_rotation_x(PI);
+
+    let (swing, twist) = swing_twist(rotation, Vec3::Z);
+
+    assert_same_rotation(swing, rotation);
+    assert_same_rotation(twist, Quat::IDENTITY);
+}
+
+#[test]
+fn angle_between_on_plane_is_signed() {
+    assert!((angle_between_on_plane(Vec3::X, Vec3::NEG_Z, Vec3::Y) - FRAC_PI_2).abs() <= EPSILON);
+    assert!((angle_between_on_plane(Vec3::NEG_Z, Vec3::X, Vec3::Y) + FRAC_PI_2).abs() <= EPSILON);
+}
+
+#[test]
+fn angle_between_on_plane_ignores_the_normal_part() {
+    let angle =
+        angle_between_on_plane(Vec3::new(1.0, 5.0, 0.0), Vec3::new(1.0, -3.0, 1.0), Vec3::Y);
+
+    assert!((angle + FRAC_PI_4).abs() <= EPSILON);
+}
+
+#[test]
+fn angle_between_on_plane_is_zero_for_vectors_along_the_normal() {
+    assert_eq!(angle_between_on_plane(Vec3::Y, Vec3::X, Vec3::Y), 0.0);
+    assert_eq!(angle_between_on_plane(Vec3::X, Vec3::NEG_Y, Vec3::Y), 0.0);
+}
+
+#[test]
+fn clamp_to_cone_keeps_directions_inside_the_cone() {
+    let direction = Vec3::new(0.1, 0.0, -1.0).normalize();
+
+    assert_same_vector(clamp_to_cone(direction, Vec3::NEG_Z, 0.5), direction);
+}
+
+#[test]
+fn clamp_to_cone_moves_directions_onto_the_edge() {
+    let clamped = clamp_to_cone(Vec3::X, Vec3::NEG_Z, FRAC_PI_4);
+
+    assert_same_vector(clamped, Vec3::new(1.0, 0.0, -1.0).normalize());
+}
+
+#[test]
+fn clamp_to_cone_handles_the_opposite_direction() {
+    let clamped = clamp_to_cone(Vec3::Z, Vec3::NEG_Z, 0.5);
+
+    assert!((clamped.angle_between(Vec3::NEG_Z) - 0.5).abs() <= EPSILON);
+}
+
+#[test]
+fn clamp_rotation_to_cone_limits_the_swing_and_keeps_the_twist() {
+    let twist = Quat::from_rotation_z(0.8);
+    let rotation = Quat::from_rotation_x(1.2) * twist;
+
+    let clamped = clamp_rotation_to_cone(rotation, Vec3::Z, 0.4);
+
+    assert_same_rotation(clamped, Quat::from_rotation_x(0.4) * twist);
+}
+
+#[test]
+fn clamp_rotation_to_cone_leaves_small_swings_alone() {
+    let rotation = Quat::from_rotation_y(0.2) * Quat::from_rotation_z(2.5);
+
+    assert_same_rotation(clamp_rotation_to_cone(rotation, Vec3::Z, 0.4), rotation);
+}
+
+#[test]
+fn clamp_twist_limits_the_twist_and_keeps_the_swing() {
+    let swing = Quat::from_rotation_x(0.6);
+
+    let clamped = clamp_twist(swing * Quat::from_rotation_z(1.5), Vec3::Z, -0.5, 0.5);
+    assert_same_rotation(clamped, swing * Quat::from_rotation_z(0.5));
+
+    let clamped = clamp_twist(swing * Quat::from_rotation_z(-1.5), Vec3::Z, -0.5, 0.5);
+    assert_same_rotation(clamped, swing * Quat::from_rotation_z(-0.5));
+}
+
+#[test]
+fn clamp_twist_measures_the_twist_the_short_way_around() {
+    // Three quarters of a turn one way is a quarter turn the other way
+    let rotation = Quat::from_rotation_z(1.5 * PI);
+
+    let clamped = clamp_twist(rotation, Vec3::Z, -FRAC_PI_2, 0.1);
+
+    assert_same_rotation(clamped, rotation);
+}
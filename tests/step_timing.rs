@@ -0,0 +1,39 @@
+#![cfg(feature = "bevy")]
+
+use walking_ik_spider::plugin::leg::StepTiming;
+
+const EPSILON: f32 = 1e-5;
+
+const TIMING: StepTiming = StepTiming {
+    foot_speed: 10.0,
+    min_duration: 0.1,
+    max_duration: 0.5,
+};
+
+#[test]
+fn longer_steps_take_longer() {
+    assert!((TIMING.step_duration(2.0, 0.0) - 0.2).abs() <= EPSILON);
+    assert!((TIMING.step_duration(4.0, 0.0) - 0.4).abs() <= EPSILON);
+}
+
+#[test]
+fn steps_speed_up_with_the_body() {
+    assert!((TIMING.step_duration(4.0, 10.0) - 0.2).abs() <= EPSILON);
+}
+
+#[test]
+fn step_duration_stays_within_bounds() {
+    assert_eq!(TIMING.step_duration(0.0, 0.0), TIMING.min_duration);
+    assert_eq!(TIMING.step_duration(100.0, 0.0), TIMING.max_duration);
+    assert_eq!(TIMING.step_duration(0.5, 100.0), TIMING.min_duration);
+}
+
+#[test]
+fn a_foot_without_speed_takes_the_longest_step() {
+    let timing = StepTiming {
+        foot_speed: 0.0,
+        ..TIMING
+    };
+
+    assert_eq!(timing.step_duration(1.0, 0.0), timing.max_duration);
+}
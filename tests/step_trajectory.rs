@@ -0,0 +1,98 @@
+#![cfg(feature = "bevy")]
+
+use bevy::prelude::Vec3;
+use walking_ik_spider::plugin::leg::trajectory::{Easing, StepCurve, StepKeyframe, StepTrajectory};
+
+const EPSILON: f32 = 1e-4;
+
+const START: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+const END: Vec3 = Vec3::new(2.0, 0.0, 0.0);
+
+fn curves() -> Vec<StepCurve> {
+    vec![
+        StepCurve::QuadraticBezier { height: 0.7 },
+        StepCurve::CubicBezier {
+            lift_height: 0.5,
+            land_height: 0.2,
+        },
+        StepCurve::SineArc { height: 0.5 },
+        StepCurve::Keyframed(vec![
+            StepKeyframe::new(0.0, 0.0),
+            StepKeyframe::new(0.5, 0.3),
+            StepKeyframe::new(1.0, 0.0),
+        ]),
+    ]
+}
+
+fn easings() -> Vec<Easing> {
+    vec![
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::Custom(|fraction| fraction.powi(3)),
+    ]
+}
+
+#[test]
+fn every_trajectory_starts_and_ends_on_the_targets() {
+    for curve in curves() {
+        for easing in easings() {
+            let trajectory = StepTrajectory::new(curve.clone(), easing);
+
+            assert!(trajectory.position(START, END, 0.0).distance(START) <= EPSILON);
+            assert!(trajectory.position(START, END, 1.0).distance(END) <= EPSILON);
+        }
+    }
+}
+
+#[test]
+fn every_curve_arcs_above_the_ground() {
+    for curve in curves() {
+        let middle = curve.position(START, END, 0.5);
+
+        assert!(middle.y > 0.0, "{:?} doesn't lift the foot", curve);
+    }
+}
+
+#[test]
+fn arc_height_is_relative_to_the_step_distance() {
+    let curve = StepCurve::SineArc { height: 0.5 };
+
+    assert!((curve.position(START, END, 0.5).y - 1.0).abs() <= EPSILON);
+    assert!((curve.position(START, END * 2.0, 0.5).y - 2.0).abs() <= EPSILON);
+}
+
+#[test]
+fn keyframes_are_interpolated_and_held_at_the_ends() {
+    let curve = StepCurve::Keyframed(vec![
+        StepKeyframe::new(0.2, 0.5),
+        StepKeyframe::new(0.6, 0.1),
+    ]);
+
+    assert!((curve.position(START, END, 0.0).y - 1.0).abs() <= EPSILON);
+    assert!((curve.position(START, END, 0.4).y - 0.6).abs() <= EPSILON);
+    assert!((curve.position(START, END, 0.8).y - 0.2).abs() <= EPSILON);
+}
+
+#[test]
+fn easings_change_the_pace_but_not_the_ends() {
+    for easing in easings() {
+        assert!(easing.apply(0.0).abs() <= EPSILON);
+        assert!((easing.apply(1.0) - 1.0).abs() <= EPSILON);
+    }
+
+    assert!(Easing::EaseIn.apply(0.3) < 0.3);
+    assert!(Easing::EaseOut.apply(0.3) > 0.3);
+    assert!(Easing::EaseInOut.apply(0.2) < 0.2);
+    assert!(Easing::EaseInOut.apply(0.8) > 0.8);
+}
+
+#[test]
+fn default_trajectory_is_the_quadratic_arc() {
+    let trajectory = StepTrajectory::default();
+
+    // The middle of a quadratic Bézier is halfway between the straight line and its control point
+    let middle = trajectory.position(START, END, 0.5);
+    assert!(middle.distance(Vec3::new(1.0, 0.7, 0.0)) <= EPSILON);
+}
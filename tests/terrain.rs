@@ -0,0 +1,138 @@
+#![cfg(feature = "bevy")]
+
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+use walking_ik_spider::plugin::{
+    ground::GroundQuery,
+    terrain::{Terrain, TerrainSettings},
+};
+
+const EPSILON: f32 = 1e-4;
+
+fn small_settings(seed: u32) -> TerrainSettings {
+    TerrainSettings {
+        seed,
+        size: 20.0,
+        resolution: 10,
+        amplitude: 3.0,
+        feature_size: 6.0,
+        octaves: 3,
+    }
+}
+
+fn grid_heights(terrain: &Terrain) -> Vec<f32> {
+    let resolution = terrain.settings().resolution;
+    (0..=resolution)
+        .flat_map(|row| (0..=resolution).map(move |column| (column, row)))
+        .map(|(column, row)| terrain.grid_height(column, row))
+        .collect()
+}
+
+#[test]
+fn the_same_seed_gives_the_same_terrain() {
+    let first = Terrain::new(small_settings(7));
+    let second = Terrain::new(small_settings(7));
+    let other = Terrain::new(small_settings(8));
+
+    assert_eq!(grid_heights(&first), grid_heights(&second));
+    assert_ne!(grid_heights(&first), grid_heights(&other));
+}
+
+#[test]
+fn heights_stay_within_the_amplitude_and_are_not_flat() {
+    let terrain = Terrain::new(small_settings(3));
+    let heights = grid_heights(&terrain);
+
+    assert!(heights.iter().all(|height| height.abs() <= 3.0));
+    let lowest = heights.iter().copied().fold(f32::INFINITY, f32::min);
+    let highest = heights.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    assert!(highest - lowest > 0.5);
+}
+
+#[test]
+fn heights_match_the_grid_points_and_are_none_off_the_terrain() {
+    let terrain = Terrain::new(small_settings(1));
+
+    // Grid points are 2 apart, starting at -10
+    let height = terrain.height_at(-6.0, 4.0).unwrap();
+    assert!((height - terrain.grid_height(2, 7)).abs() <= EPSILON);
+    let height = terrain.height_at(10.0, 10.0).unwrap();
+    assert!((height - terrain.grid_height(10, 10)).abs() <= EPSILON);
+
+    assert_eq!(terrain.height_at(10.5, 0.0), None);
+    assert_eq!(terrain.height_at(0.0, -11.0), None);
+}
+
+#[test]
+fn heights_between_grid_points_follow_the_mesh_triangles() {
+    let terrain = Terrain::new(small_settings(5));
+    let first = terrain.grid_height(4, 4);
+    let next_column = terrain.grid_height(5, 4);
+    let next_row = terrain.grid_height(4, 5);
+    let last = terrain.grid_height(5, 5);
+
+    // The cell from (-2, -2) to (0, 0) is split along its diagonal
+    let on_diagonal = terrain.height_at(-1.0, -1.0).unwrap();
+    assert!((on_diagonal - (first + last) / 2.0).abs() <= EPSILON);
+
+    let along_x = terrain.height_at(-1.0, -2.0).unwrap();
+    assert!((along_x - (first + next_column) / 2.0).abs() <= EPSILON);
+
+    let along_z = terrain.height_at(-2.0, -1.0).unwrap();
+    assert!((along_z - (first + next_row) / 2.0).abs() <= EPSILON);
+}
+
+#[test]
+fn normals_point_up_the_slope() {
+    let terrain = Terrain::new(small_settings(2));
+
+    for (x, z) in [(-3.3, 1.2), (0.4, -7.9), (5.5, 5.1)] {
+        let normal = terrain.normal_at(x, z).unwrap();
+        let step = 0.01;
+        let downhill = Vec3::new(normal.x, 0.0, normal.z) * step;
+        let here = terrain.height_at(x, z).unwrap();
+        let there = terrain.height_at(x + downhill.x, z + downhill.z).unwrap();
+
+        assert!(normal.y > 0.0);
+        assert!((normal.length() - 1.0).abs() <= EPSILON);
+        assert!(there <= here + EPSILON);
+    }
+}
+
+#[test]
+fn feet_are_placed_on_the_terrain() {
+    let terrain = Terrain::new(small_settings(4));
+    let height = terrain.height_at(1.3, -2.7).unwrap();
+
+    let placed = terrain.place_on_ground(Vec3::new(1.3, height + 1.0, -2.7));
+    assert!(placed.distance(Vec3::new(1.3, height, -2.7)) <= EPSILON);
+
+    let hit = terrain
+        .raycast_down(Vec3::new(1.3, height + 2.0, -2.7), 5.0)
+        .unwrap();
+    assert!((hit.distance - 2.0).abs() <= EPSILON);
+    assert_eq!(hit.normal, terrain.normal_at(1.3, -2.7).unwrap());
+
+    assert_eq!(
+        terrain.raycast_down(Vec3::new(1.3, height - 0.1, -2.7), 5.0),
+        None
+    );
+    assert_eq!(terrain.raycast_down(Vec3::new(15.0, 0.0, 0.0), 5.0), None);
+}
+
+#[test]
+fn the_mesh_has_a_vertex_per_grid_point() {
+    let terrain = Terrain::new(small_settings(6));
+    let mesh = terrain.mesh();
+
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        panic!("terrain mesh has no positions");
+    };
+    assert_eq!(positions.len(), 11 * 11);
+    assert_eq!(mesh.indices().unwrap().len(), 10 * 10 * 6);
+
+    for [x, y, z] in positions {
+        assert!((terrain.height_at(*x, *z).unwrap() - y).abs() <= EPSILON);
+    }
+}